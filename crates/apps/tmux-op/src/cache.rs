@@ -0,0 +1,67 @@
+use crate::project_finder::ProjectInfo;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::{fs, io};
+
+/// On-disk index of discovered projects, keyed by project directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectIndex {
+    projects: BTreeMap<String, ProjectInfo>,
+}
+
+impl ProjectIndex {
+    /// Loads the index, falling back to an empty one if it is missing or unreadable.
+    pub fn load() -> Self {
+        let Ok(index_path) = Self::get_index_path() else {
+            return Self::default();
+        };
+
+        fs::read_to_string(index_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let index_path = Self::get_index_path()?;
+
+        if let Some(parent) = index_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Written aside and moved into place, so an interrupted save never
+        // leaves a truncated index behind
+        let temp_path = index_path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string(self)?)?;
+        fs::rename(temp_path, index_path)?;
+        Ok(())
+    }
+
+    pub fn from_projects(projects: &[ProjectInfo]) -> Self {
+        let projects = projects
+            .iter()
+            .map(|project| (project.directory.clone(), project.clone()))
+            .collect();
+        ProjectIndex { projects }
+    }
+
+    pub fn projects(&self) -> Vec<ProjectInfo> {
+        self.projects.values().cloned().collect()
+    }
+
+    /// Returns the cached project for `directory` if its `.dexproject` hasn't
+    /// changed since it was indexed.
+    pub fn lookup(&self, directory: &str, dexproject_mtime: Option<u64>) -> Option<&ProjectInfo> {
+        self.projects.get(directory).filter(|project| {
+            dexproject_mtime.is_some() && project.dexproject_mtime == dexproject_mtime
+        })
+    }
+
+    fn get_index_path() -> io::Result<PathBuf> {
+        let cache_dir = dirs::cache_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No cache directory found"))?;
+        Ok(cache_dir.join("dex").join("tmux-op").join("projects.json"))
+    }
+}
@@ -0,0 +1,389 @@
+use crate::cache::ProjectIndex;
+use crate::config::{Config, OpenMode, SearchField};
+use crate::history::History;
+use crate::multiplexer;
+use crate::project_finder::{find_project_files, ProjectInfo};
+use crate::search::rank_projects;
+use anyhow::{bail, Result};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use serde_json::json;
+use std::io;
+use std::path::PathBuf;
+
+const DEFAULT_FORMAT: &str = "{name}\t{directory}";
+
+/// How many candidates to show when a query is ambiguous.
+const MAX_CANDIDATES: usize = 10;
+
+/// Fuzzy find projects and open them in tmux
+#[derive(Debug, Parser)]
+#[command(name = "tmux-op", version)]
+pub struct Cli {
+    /// Print debug information
+    #[arg(long, global = true)]
+    pub debug: bool,
+
+    /// Use a different config file
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Create a new project
+    Mk(Box<MkArgs>),
+    /// Rebuild the project index from scratch
+    Scan,
+    /// Print discovered projects
+    List {
+        /// Print a JSON array instead of one line per project
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+        /// Line template using {name}, {language} and {directory}
+        #[arg(long, value_name = "TEMPLATE", default_value = DEFAULT_FORMAT)]
+        format: String,
+        /// Only read the project index instead of scanning
+        #[arg(long)]
+        cached: bool,
+    },
+    /// Open the best matching project without the browser
+    Open {
+        /// Project name or fuzzy query
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Pick the best match instead of failing when several projects match
+        #[arg(long)]
+        first: bool,
+    },
+    /// Print the directory the best matching project opens in
+    Path {
+        /// Project name or fuzzy query
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Pick the best match instead of failing when several projects match
+        #[arg(long)]
+        first: bool,
+    },
+    /// Manage the history used to rank projects
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+    /// Print a shell completion script
+    Completions {
+        #[arg(value_enum)]
+        shell: CompletionShell,
+    },
+}
+
+/// Options of `tmux-op mk`. Passing `--language` writes the project without
+/// showing the form.
+#[derive(Debug, Args)]
+pub struct MkArgs {
+    /// Create the project in a new directory under a search path, from
+    /// its language's template
+    #[arg(long)]
+    pub new: bool,
+    /// Start from this template, one of the built-in languages or a
+    /// directory in ~/.config/dex/templates. Implies --new
+    #[arg(long, value_name = "TEMPLATE")]
+    pub template: Option<String>,
+    /// Project name, defaults to the directory's name
+    #[arg(long, requires = "language")]
+    pub name: Option<String>,
+    /// Project language, skipping the form
+    #[arg(long)]
+    pub language: Option<String>,
+    /// Comma separated tags
+    #[arg(long, value_delimiter = ',')]
+    pub tags: Vec<String>,
+    /// One line summary shown in the browser
+    #[arg(long)]
+    pub description: Option<String>,
+    /// Editor the project opens in, also exported as EDITOR
+    #[arg(long)]
+    pub editor: Option<String>,
+    /// Named shell command, like build="cargo build". Can be repeated
+    #[arg(long = "command", value_name = "NAME=COMMAND")]
+    pub commands: Vec<String>,
+    /// Variable to export into the project's panes. Can be repeated
+    #[arg(long = "env", value_name = "NAME=VALUE")]
+    pub env: Vec<String>,
+    /// Directory to open the project in, relative to the .dexproject
+    #[arg(long, value_name = "PATH")]
+    pub root: Option<String>,
+    /// Leave the project out of the browser unless hidden projects are shown
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub hidden: Option<bool>,
+    /// Rewrite an existing .dexproject without asking, keeping the fields
+    /// that aren't given
+    #[arg(long)]
+    pub force: bool,
+    /// Directory to write the .dexproject in instead of the current one, or
+    /// to create with --new
+    #[arg(long, value_name = "PATH")]
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryAction {
+    /// Forget every recorded visit
+    Clear,
+    /// Forget old visits and visits to projects that no longer exist
+    Prune {
+        /// Forget visits older than this many days
+        #[arg(long, value_name = "DAYS", default_value_t = 90)]
+        older_than: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Scans for projects, refreshing the index along the way.
+fn load_projects(config: &Config) -> Result<Vec<ProjectInfo>> {
+    let index = ProjectIndex::load();
+    let mut projects = find_project_files(
+        &config.search_paths,
+        &config.detection_rules(),
+        Some(&index),
+    )?;
+    ProjectIndex::from_projects(&projects).save()?;
+
+    projects.sort_by_key(|project| project.name.to_lowercase());
+    Ok(projects)
+}
+
+/// Reads projects from the index alone, only scanning if nothing is indexed yet.
+fn load_cached_projects(config: &Config) -> Result<Vec<ProjectInfo>> {
+    let mut projects = ProjectIndex::load().projects();
+    if projects.is_empty() {
+        return load_projects(config);
+    }
+
+    projects.sort_by_key(|project| project.name.to_lowercase());
+    Ok(projects)
+}
+
+/// Fills in `{name}`, `{language}` and `{directory}`, plus `\t` and `\n`
+/// escapes since those are awkward to type in a shell.
+fn render_template(format: &str, project: &ProjectInfo) -> String {
+    format
+        .replace("\\t", "\t")
+        .replace("\\n", "\n")
+        .replace("{name}", &project.name)
+        .replace("{language}", &project.language)
+        .replace("{directory}", &project.directory)
+}
+
+/// Finds the single project `query` refers to. An exact name match wins,
+/// otherwise the query must fuzzy match exactly one project unless `first`
+/// allows picking the best of several. Names are matched before the other
+/// search fields, since every directory shares its search path's prefix.
+fn resolve<'a>(
+    config: &Config,
+    projects: &'a [ProjectInfo],
+    query: &str,
+    first: bool,
+    history: &History,
+) -> Result<&'a ProjectInfo> {
+    let exact: Vec<&ProjectInfo> = projects
+        .iter()
+        .filter(|project| project.name.eq_ignore_ascii_case(query))
+        .collect();
+    if exact.len() == 1 || (first && !exact.is_empty()) {
+        return Ok(exact[0]);
+    }
+
+    let candidates: Vec<&ProjectInfo> = if exact.is_empty() {
+        let matcher = SkimMatcherV2::default();
+        let scores = history.scores();
+        let mut matches = rank_projects(&matcher, projects, query, &[SearchField::Name], &scores);
+        if matches.is_empty() {
+            matches = rank_projects(&matcher, projects, query, &config.search_fields, &scores);
+        }
+        matches.into_iter().map(|m| &projects[m.index]).collect()
+    } else {
+        exact
+    };
+
+    match candidates.as_slice() {
+        [] => bail!("No project matches '{}'", query),
+        [project] => Ok(project),
+        [best, ..] if first => Ok(best),
+        _ => {
+            let listing = candidates
+                .iter()
+                .take(MAX_CANDIDATES)
+                .map(|project| format!("  {}\t{}", project.name, project.directory))
+                .collect::<Vec<_>>()
+                .join("\n");
+            bail!(
+                "'{}' matches {} projects:\n{}\nUse --first to pick the best match",
+                query,
+                candidates.len(),
+                listing
+            )
+        }
+    }
+}
+
+/// `tmux-op list`
+pub fn list(config: &Config, json_output: bool, format: &str, cached: bool) -> Result<()> {
+    let projects = if cached {
+        load_cached_projects(config)?
+    } else {
+        load_projects(config)?
+    };
+
+    if json_output {
+        let entries: Vec<_> = projects
+            .iter()
+            .map(|project| {
+                json!({
+                    "name": project.name,
+                    "language": project.language,
+                    "directory": project.directory,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        for project in &projects {
+            println!("{}", render_template(format, project));
+        }
+    }
+
+    Ok(())
+}
+
+/// Opens `project` with the configured multiplexer, as a session, window or
+/// split depending on `mode`, and records the visit.
+pub fn open_project(config: &Config, project: &ProjectInfo, mode: OpenMode) -> Result<()> {
+    // Record first, attaching from outside a multiplexer blocks until detach
+    let mut history = History::load();
+    history.record(&project.directory);
+    history.save()?;
+
+    let mux = multiplexer::detect(config.multiplexer);
+    multiplexer::open_project(mux.as_ref(), project, mode, config.layout.as_ref())?;
+    Ok(())
+}
+
+/// `tmux-op open`
+pub fn open(config: &Config, query: &[String], first: bool) -> Result<()> {
+    let projects = load_projects(config)?;
+    let project = resolve(config, &projects, &query.join(" "), first, &History::load())?;
+    open_project(config, project, config.open_mode)
+}
+
+/// `tmux-op path`
+pub fn path(config: &Config, query: &[String], first: bool) -> Result<()> {
+    let projects = load_projects(config)?;
+    let project = resolve(config, &projects, &query.join(" "), first, &History::load())?;
+    println!("{}", project.open_directory());
+    Ok(())
+}
+
+/// `tmux-op history`
+pub fn history(action: HistoryAction) -> Result<()> {
+    let mut history = History::load();
+    match action {
+        HistoryAction::Clear => {
+            history.clear();
+            println!("Cleared project history");
+        }
+        HistoryAction::Prune { older_than } => {
+            let removed = history.prune(older_than);
+            println!("Removed {} visits", removed);
+        }
+    }
+    history.save()
+}
+
+// Project names can't be known when the script is generated, so each shell
+// gets a small wrapper that asks `tmux-op list --cached` for them when
+// completing `open` or `path`.
+const BASH_PROJECT_COMPLETION: &str = r#"
+_tmux__op_projects() {
+    local i cmd=""
+    for (( i = 1; i < COMP_CWORD; i++ )); do
+        case "${COMP_WORDS[i]}" in
+            open|path) cmd="${COMP_WORDS[i]}"; break ;;
+        esac
+    done
+
+    if [[ -n "$cmd" && "${COMP_WORDS[COMP_CWORD]}" != -* ]]; then
+        local IFS=$'\n'
+        COMPREPLY=( $(compgen -W "$(tmux-op list --cached --format '{name}' 2>/dev/null)" -- "${COMP_WORDS[COMP_CWORD]}") )
+        return 0
+    fi
+
+    _tmux__op "$@"
+}
+
+complete -F _tmux__op_projects -o bashdefault -o default tmux-op
+"#;
+
+const ZSH_PROJECT_COMPLETION: &str = r#"
+_tmux-op_projects() {
+    if (( CURRENT > 2 )) && (( ${words[(I)open|path]} )) && [[ ${words[CURRENT]} != -* ]]; then
+        local -a projects
+        projects=(${(f)"$(tmux-op list --cached --format '{name}' 2>/dev/null)"})
+        compadd -a projects
+        return
+    fi
+
+    _tmux-op "$@"
+}
+
+compdef _tmux-op_projects tmux-op
+"#;
+
+const FISH_PROJECT_COMPLETION: &str = r#"
+complete -c tmux-op -n "__fish_seen_subcommand_from open path" -f -a "(tmux-op list --cached --format '{name}' 2>/dev/null)"
+"#;
+
+/// `tmux-op completions`
+pub fn completions(shell: CompletionShell) {
+    let mut command = Cli::command();
+    let mut stdout = io::stdout();
+
+    match shell {
+        CompletionShell::Bash => {
+            clap_complete::generate(
+                clap_complete::Shell::Bash,
+                &mut command,
+                "tmux-op",
+                &mut stdout,
+            );
+            print!("{}", BASH_PROJECT_COMPLETION);
+        }
+        CompletionShell::Zsh => {
+            clap_complete::generate(
+                clap_complete::Shell::Zsh,
+                &mut command,
+                "tmux-op",
+                &mut stdout,
+            );
+            print!("{}", ZSH_PROJECT_COMPLETION);
+        }
+        CompletionShell::Fish => {
+            clap_complete::generate(
+                clap_complete::Shell::Fish,
+                &mut command,
+                "tmux-op",
+                &mut stdout,
+            );
+            print!("{}", FISH_PROJECT_COMPLETION);
+        }
+    }
+}
@@ -1,12 +1,239 @@
-use anyhow::Result;
-use dirs;
+use crate::keys::KeysConfig;
+use crate::layout::Layout;
+use crate::theme::ThemeConfig;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{fs, io};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub search_paths: Vec<PathBuf>,
+    /// Marker files used to detect projects without a `.dexproject`.
+    /// Rules are checked in order, so the first match decides the language.
+    #[serde(default = "default_detection")]
+    pub detection: Vec<DetectionRule>,
+    #[serde(default)]
+    pub open_mode: OpenMode,
+    #[serde(default)]
+    pub multiplexer: MultiplexerKind,
+    /// Layout for projects that don't define their own in `.dexproject`
+    pub layout: Option<Layout>,
+    /// Show the preview pane when the browser opens
+    #[serde(default)]
+    pub preview: bool,
+    /// Order of the browser's list while nothing is searched
+    #[serde(default)]
+    pub sort: SortKey,
+    /// Project fields that plain search terms are matched against
+    #[serde(default = "default_search_fields")]
+    pub search_fields: Vec<SearchField>,
+    /// How the browser groups projects under collapsible headers
+    #[serde(default)]
+    pub group_by: GroupBy,
+    /// Languages to add to or override the built-in ones
+    #[serde(default)]
+    pub languages: Vec<LanguageConfig>,
+    /// Which icons to show next to projects
+    #[serde(default)]
+    pub icons: IconSet,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Keys for the browser's and the `mk` form's actions, over the defaults
+    #[serde(default)]
+    pub keys: KeysConfig,
+    /// Click, double click and scroll in the browser. Turn off to select text
+    /// with the mouse instead
+    #[serde(default = "default_mouse")]
+    pub mouse: bool,
+    /// Filled in for `{{author}}` in templates, git's `user.name` if unset
+    pub author: Option<String>,
+}
+
+/// How a selected project is opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenMode {
+    /// A dedicated session per project, reused if it is already running
+    #[default]
+    Session,
+    /// A new window in the current session
+    Window,
+    /// A pane split off the current one, without the project's layout
+    Split,
+}
+
+/// How the browser orders projects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Most used projects first
+    #[default]
+    Frecency,
+    Name,
+    /// Most recently committed to first
+    Committed,
+    /// Projects with uncommitted changes first
+    Dirty,
+}
+
+impl SortKey {
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Frecency => SortKey::Name,
+            SortKey::Name => SortKey::Committed,
+            SortKey::Committed => SortKey::Dirty,
+            SortKey::Dirty => SortKey::Frecency,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Frecency => "frecency",
+            SortKey::Name => "name",
+            SortKey::Committed => "last commit",
+            SortKey::Dirty => "uncommitted changes",
+        }
+    }
+
+    /// Whether the order depends on git status.
+    pub fn uses_git(self) -> bool {
+        matches!(self, SortKey::Committed | SortKey::Dirty)
+    }
+}
+
+/// Which glyphs are used as language icons.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconSet {
+    /// Nerd Font glyphs, which need a patched font
+    #[default]
+    Nerd,
+    /// Symbols most fonts have
+    Unicode,
+    /// Short codes like `[rs]`
+    Ascii,
+}
+
+/// What the browser groups projects by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// A flat list
+    #[default]
+    None,
+    Language,
+    /// The search path a project was found under
+    Root,
+}
+
+impl GroupBy {
+    pub fn next(self) -> Self {
+        match self {
+            GroupBy::None => GroupBy::Language,
+            GroupBy::Language => GroupBy::Root,
+            GroupBy::Root => GroupBy::None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GroupBy::None => "nothing",
+            GroupBy::Language => "language",
+            GroupBy::Root => "search path",
+        }
+    }
+}
+
+/// A project field searched by plain search terms.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchField {
+    Name,
+    Directory,
+    Language,
+    Tags,
+    Description,
+}
+
+fn default_search_fields() -> Vec<SearchField> {
+    vec![SearchField::Name, SearchField::Directory]
+}
+
+fn default_mouse() -> bool {
+    true
+}
+
+/// Which multiplexer projects are opened in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiplexerKind {
+    /// Whichever one tmux-op is running inside, or none
+    #[default]
+    Auto,
+    Tmux,
+    Zellij,
+    /// Just start a shell in the project directory
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionRule {
+    /// File or directory name to look for. A leading `*` matches any prefix,
+    /// e.g. `*.csproj`.
+    pub marker: String,
+    pub language: String,
+}
+
+/// A language added to or overriding one of the built-in languages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageConfig {
+    /// The first name is the one shown, the rest are aliases
+    pub names: Vec<String>,
+    /// Icon for the `nerd` icon set
+    pub icon: Option<String>,
+    /// Icon for the `unicode` icon set
+    pub unicode_icon: Option<String>,
+    /// Icon for the `ascii` icon set, defaults to a code like `[py]`
+    pub ascii_icon: Option<String>,
+    /// Icon colour, a name like `red`, a 256 colour index or a hex code like `#dea584`
+    pub color: Option<String>,
+    /// Files or directories that mark a project in this language, matched
+    /// like `detection` markers and checked before them
+    #[serde(default)]
+    pub markers: Vec<String>,
+    /// Extensions of source files without the dot, like `py`, used to suggest
+    /// a language in `mk`
+    #[serde(default)]
+    pub extensions: Vec<String>,
+}
+
+impl DetectionRule {
+    fn new(marker: &str, language: &str) -> Self {
+        DetectionRule {
+            marker: marker.to_string(),
+            language: language.to_string(),
+        }
+    }
+
+    pub fn matches(&self, file_name: &str) -> bool {
+        match self.marker.strip_prefix('*') {
+            Some(suffix) => file_name.ends_with(suffix),
+            None => file_name == self.marker,
+        }
+    }
+}
+
+fn default_detection() -> Vec<DetectionRule> {
+    vec![
+        DetectionRule::new("Cargo.toml", "Rust"),
+        DetectionRule::new("go.mod", "Go"),
+        DetectionRule::new("tsconfig.json", "Typescript"),
+        DetectionRule::new("package.json", "Javascript"),
+        DetectionRule::new("*.csproj", "C#"),
+        DetectionRule::new("CMakeLists.txt", "C++"),
+        DetectionRule::new(".git", "UNKNOWN"),
+    ]
 }
 
 impl Default for Config {
@@ -20,27 +247,64 @@ impl Default for Config {
             search_paths = vec![docs];
         }
 
-        Config { search_paths }
+        Config {
+            search_paths,
+            detection: default_detection(),
+            open_mode: OpenMode::default(),
+            multiplexer: MultiplexerKind::default(),
+            layout: None,
+            preview: false,
+            sort: SortKey::default(),
+            search_fields: default_search_fields(),
+            group_by: GroupBy::default(),
+            languages: Vec::new(),
+            icons: IconSet::default(),
+            theme: ThemeConfig::default(),
+            keys: KeysConfig::new(),
+            mouse: default_mouse(),
+            author: None,
+        }
     }
 }
 
 impl Config {
-    pub fn load() -> Result<Self> {
-        let config_path = Self::get_config_path()?;
+    /// Detection rules for the configured languages' markers followed by
+    /// the `detection` rules.
+    pub fn detection_rules(&self) -> Vec<DetectionRule> {
+        let mut rules: Vec<DetectionRule> = self
+            .languages
+            .iter()
+            .filter_map(|language| Some((language.names.first()?, &language.markers)))
+            .flat_map(|(name, markers)| {
+                markers
+                    .iter()
+                    .map(move |marker| DetectionRule::new(marker, name))
+            })
+            .collect();
+        rules.extend(self.detection.iter().cloned());
+        rules
+    }
+
+    /// Loads the config from `path`, or the default location if that is `None`,
+    /// writing out the defaults first if the file doesn't exist yet.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let config_path = match path {
+            Some(path) => path.to_path_buf(),
+            None => Self::get_config_path()?,
+        };
 
         if !config_path.exists() {
             let config = Config::default();
-            config.save()?;
+            config.save(&config_path)?;
             return Ok(config);
         }
 
         let content = fs::read_to_string(&config_path)?;
-        Ok(toml::from_str(&content)?)
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", config_path.display()))
     }
 
-    pub fn save(&self) -> Result<()> {
-        let config_path = Self::get_config_path()?;
-
+    pub fn save(&self, config_path: &Path) -> Result<()> {
         // Ensure config directory exists
         if let Some(parent) = config_path.parent() {
             fs::create_dir_all(parent)?;
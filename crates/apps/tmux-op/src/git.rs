@@ -0,0 +1,151 @@
+use rayon::prelude::*;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Branch and working tree state of a repository.
+#[derive(Debug, Clone)]
+pub struct GitStatus {
+    /// `None` on a detached HEAD
+    pub branch: Option<String>,
+    pub dirty: bool,
+    /// Commits ahead of and behind the upstream, `None` without an upstream
+    pub ahead_behind: Option<(u32, u32)>,
+    /// Committer time of HEAD in seconds since the epoch, `None` before the first commit
+    pub last_commit_time: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub summary: String,
+    pub relative_date: String,
+}
+
+/// Runs git in `directory`, returning its output if it succeeded.
+fn git(directory: &str, args: &[&str]) -> Option<String> {
+    // Optional locks would make a refresh race with git commands the user runs
+    let output = Command::new("git")
+        .arg("--no-optional-locks")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Reads the state of the repository in `directory`, or `None` if it isn't
+/// one. Only the local repository is read, the upstream is compared against
+/// whatever was last fetched.
+pub fn status(directory: &str) -> Option<GitStatus> {
+    let output = git(directory, &["status", "--porcelain=v2", "--branch"])?;
+
+    let mut status = GitStatus {
+        branch: None,
+        dirty: false,
+        ahead_behind: None,
+        last_commit_time: None,
+    };
+    for line in output.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            if head != "(detached)" {
+                status.branch = Some(head.to_string());
+            }
+        } else if let Some(counts) = line.strip_prefix("# branch.ab ") {
+            // Formatted as `+<ahead> -<behind>`
+            status.ahead_behind = counts.split_once(' ').and_then(|(ahead, behind)| {
+                Some((
+                    ahead.trim_start_matches('+').parse().ok()?,
+                    behind.trim_start_matches('-').parse().ok()?,
+                ))
+            });
+        } else if !line.starts_with('#') {
+            status.dirty = true;
+        }
+    }
+
+    status.last_commit_time = status
+        .branch
+        .is_some()
+        .then(|| git(directory, &["log", "-1", "--format=%ct"]))
+        .flatten()
+        .and_then(|output| output.trim().parse().ok());
+
+    Some(status)
+}
+
+pub fn last_commit(directory: &str) -> Option<Commit> {
+    let output = git(directory, &["log", "-1", "--format=%s%x00%cr"])?;
+    let (summary, relative_date) = output.trim_end().split_once('\0')?;
+
+    Some(Commit {
+        summary: summary.to_string(),
+        relative_date: relative_date.to_string(),
+    })
+}
+
+/// Reads git status for projects on a background thread so the list can show
+/// it without waiting on every repository up front.
+pub struct StatusLoader {
+    requests: Sender<String>,
+    results: Receiver<(String, Option<GitStatus>)>,
+    pending: usize,
+}
+
+impl StatusLoader {
+    pub fn new() -> Self {
+        let (requests, request_receiver) = mpsc::channel::<String>();
+        let (result_sender, results) = mpsc::channel();
+
+        thread::spawn(move || {
+            while let Ok(directory) = request_receiver.recv() {
+                // Read everything queued so far in parallel
+                let mut batch = vec![directory];
+                batch.extend(request_receiver.try_iter());
+
+                let statuses: Vec<(String, Option<GitStatus>)> = batch
+                    .into_par_iter()
+                    .map(|directory| {
+                        let status = status(&directory);
+                        (directory, status)
+                    })
+                    .collect();
+                for result in statuses {
+                    if result_sender.send(result).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        StatusLoader {
+            requests,
+            results,
+            pending: 0,
+        }
+    }
+
+    pub fn request(&mut self, directory: &str) {
+        if self.requests.send(directory.to_string()).is_ok() {
+            self.pending += 1;
+        }
+    }
+
+    /// Statuses that finished loading since the last call, `None` for
+    /// directories that aren't repositories.
+    pub fn poll(&mut self) -> Vec<(String, Option<GitStatus>)> {
+        let results: Vec<_> = self.results.try_iter().collect();
+        self.pending = self.pending.saturating_sub(results.len());
+        results
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending > 0
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+/// Oldest visits are dropped once the history grows past this.
+const MAX_VISITS: usize = 1000;
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Visit {
+    directory: String,
+    /// Seconds since the epoch
+    timestamp: u64,
+}
+
+/// Every time a project was opened, used to rank projects by frecency.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    visits: Vec<Visit>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// How much a single visit is worth, recent visits counting the most.
+fn visit_weight(age: u64) -> u64 {
+    match age {
+        age if age < 4 * DAY => 100,
+        age if age < 14 * DAY => 70,
+        age if age < 31 * DAY => 50,
+        age if age < 90 * DAY => 30,
+        _ => 10,
+    }
+}
+
+impl History {
+    /// Loads the history, falling back to an empty one if it is missing or unreadable.
+    pub fn load() -> Self {
+        let Ok(history_path) = Self::get_history_path() else {
+            return Self::default();
+        };
+
+        fs::read_to_string(history_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let history_path = Self::get_history_path()?;
+
+        if let Some(parent) = history_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(history_path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Records that the project in `directory` was just opened.
+    pub fn record(&mut self, directory: &str) {
+        self.visits.push(Visit {
+            directory: directory.to_string(),
+            timestamp: now(),
+        });
+
+        if self.visits.len() > MAX_VISITS {
+            let excess = self.visits.len() - MAX_VISITS;
+            self.visits.drain(..excess);
+        }
+    }
+
+    /// Frecency score for every directory that has been visited.
+    pub fn scores(&self) -> HashMap<String, u64> {
+        let now = now();
+        let mut scores = HashMap::new();
+        for visit in &self.visits {
+            *scores.entry(visit.directory.clone()).or_default() +=
+                visit_weight(now.saturating_sub(visit.timestamp));
+        }
+        scores
+    }
+
+    pub fn clear(&mut self) {
+        self.visits.clear();
+    }
+
+    /// Drops visits older than `max_age_days` and visits to directories that
+    /// no longer exist, returning how many were removed.
+    pub fn prune(&mut self, max_age_days: u64) -> usize {
+        let cutoff = now().saturating_sub(max_age_days * DAY);
+        let before = self.visits.len();
+        self.visits
+            .retain(|visit| visit.timestamp >= cutoff && Path::new(&visit.directory).is_dir());
+        before - self.visits.len()
+    }
+
+    fn get_history_path() -> io::Result<PathBuf> {
+        let data_dir = dirs::data_local_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory found"))?;
+        Ok(data_dir.join("dex").join("tmux-op").join("history.json"))
+    }
+}
@@ -0,0 +1,430 @@
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Something a key binding can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    First,
+    Last,
+    Search,
+    Open,
+    /// Open the project in a pane split off the current one
+    OpenInSplit,
+    TogglePreview,
+    CycleSort,
+    FilterLanguage,
+    CycleGrouping,
+    /// Show or leave out projects marked `hidden`
+    ToggleHidden,
+    /// Fold or unfold the selected group
+    ToggleGroup,
+    Collapse,
+    Expand,
+    Help,
+    /// Switch between the fields of the `mk` form
+    NextField,
+}
+
+impl Action {
+    /// Every action, in the order the help lists them.
+    pub const ALL: [Action; 22] = [
+        Action::Down,
+        Action::Up,
+        Action::PageDown,
+        Action::PageUp,
+        Action::HalfPageDown,
+        Action::HalfPageUp,
+        Action::First,
+        Action::Last,
+        Action::Search,
+        Action::Open,
+        Action::OpenInSplit,
+        Action::TogglePreview,
+        Action::CycleSort,
+        Action::FilterLanguage,
+        Action::CycleGrouping,
+        Action::ToggleHidden,
+        Action::ToggleGroup,
+        Action::Collapse,
+        Action::Expand,
+        Action::Help,
+        Action::Quit,
+        Action::NextField,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Down => "move down",
+            Action::Up => "move up",
+            Action::PageDown => "page down",
+            Action::PageUp => "page up",
+            Action::HalfPageDown => "half a page down",
+            Action::HalfPageUp => "half a page up",
+            Action::First => "first project",
+            Action::Last => "last project",
+            Action::Search => "search",
+            Action::Open => "open",
+            Action::OpenInSplit => "open in a split",
+            Action::TogglePreview => "toggle the preview",
+            Action::CycleSort => "change the sort order",
+            Action::FilterLanguage => "filter by language",
+            Action::CycleGrouping => "change the grouping",
+            Action::ToggleHidden => "show or leave out hidden projects",
+            Action::ToggleGroup => "fold or unfold a group",
+            Action::Collapse => "fold a group",
+            Action::Expand => "unfold a group",
+            Action::Help => "show the key bindings",
+            Action::NextField => "next field",
+        }
+    }
+
+    fn defaults(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q", "esc", "ctrl-c"],
+            Action::Down => &["j", "down", "ctrl-n"],
+            Action::Up => &["k", "up", "ctrl-p"],
+            Action::PageDown => &["pagedown"],
+            Action::PageUp => &["pageup"],
+            Action::HalfPageDown => &["ctrl-d"],
+            Action::HalfPageUp => &["ctrl-u"],
+            Action::First => &["home", "g g"],
+            Action::Last => &["end", "G"],
+            Action::Search => &["/"],
+            Action::Open => &["enter"],
+            Action::OpenInSplit => &["ctrl-v"],
+            Action::TogglePreview => &["p"],
+            Action::CycleSort => &["s"],
+            Action::FilterLanguage => &["f"],
+            Action::CycleGrouping => &["v"],
+            Action::ToggleHidden => &["H"],
+            Action::ToggleGroup => &["tab", "space"],
+            Action::Collapse => &["h", "left"],
+            Action::Expand => &["l", "right"],
+            Action::Help => &["?"],
+            Action::NextField => &["tab"],
+        }
+    }
+
+    pub fn is_available(self, context: Context) -> bool {
+        match context {
+            Context::Browser => self != Action::NextField,
+            Context::Search => !matches!(self, Action::NextField | Action::Search | Action::Help),
+            Context::Form => matches!(
+                self,
+                Action::Quit | Action::Open | Action::NextField | Action::Down | Action::Up
+            ),
+        }
+    }
+}
+
+/// Where keys are being pressed, which decides the actions that apply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Context {
+    /// The project browser
+    Browser,
+    /// The browser while a search is typed, where characters go to the query
+    Search,
+    /// The `mk` form, where characters go to the focused field
+    Form,
+}
+
+impl Context {
+    /// Whether plain characters are typed rather than looked up.
+    fn is_typing(self) -> bool {
+        matches!(self, Context::Search | Context::Form)
+    }
+}
+
+/// A key with its modifiers, written like `j`, `G`, `ctrl-d` or `pagedown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of the character, or of `BackTab`
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        KeyChord { code, modifiers }
+    }
+
+    /// Whether typing this would enter a character.
+    pub fn is_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        // A lone `-` is a key rather than a separator
+        while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        Some(KeyChord::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        KeyChord::new(key.code, key.modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => {
+                let name = match code {
+                    KeyCode::Enter => "enter",
+                    KeyCode::Esc => "esc",
+                    KeyCode::Tab => "tab",
+                    KeyCode::BackTab => "shift-tab",
+                    KeyCode::Backspace => "backspace",
+                    KeyCode::Delete => "delete",
+                    KeyCode::Up => "up",
+                    KeyCode::Down => "down",
+                    KeyCode::Left => "left",
+                    KeyCode::Right => "right",
+                    KeyCode::Home => "home",
+                    KeyCode::End => "end",
+                    KeyCode::PageUp => "pageup",
+                    KeyCode::PageDown => "pagedown",
+                    _ => "?",
+                };
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+/// Keys bound to one action, either a single sequence or a list of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The `[keys]` section of the config, replacing the default keys of each
+/// action it lists, e.g. `open-in-split = ["ctrl-v", "V"]`. A sequence of keys
+/// is written with spaces, like `g g`.
+pub type KeysConfig = HashMap<Action, Keys>;
+
+#[derive(Debug, Clone)]
+struct Binding {
+    keys: Vec<KeyChord>,
+    action: Action,
+}
+
+/// What a sequence of pressed keys resolved to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lookup {
+    Action(Action),
+    /// The start of a longer binding, wait for the next key
+    Pending,
+    None,
+}
+
+/// The active key bindings.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&KeysConfig::new()).expect("default key bindings are valid")
+    }
+}
+
+fn format_keys(keys: &[KeyChord]) -> String {
+    keys.iter()
+        .map(KeyChord::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Keymap {
+    /// Applies `config` over the default bindings, failing with every key that
+    /// can't be parsed and every key bound to more than one action.
+    pub fn new(config: &KeysConfig) -> Result<Self> {
+        let mut bindings = Vec::new();
+        let mut problems = Vec::new();
+
+        for action in Action::ALL {
+            let sequences: Vec<&str> = match config.get(&action) {
+                Some(Keys::One(keys)) => vec![keys.as_str()],
+                Some(Keys::Many(keys)) => keys.iter().map(String::as_str).collect(),
+                None => action.defaults().to_vec(),
+            };
+            for sequence in sequences {
+                let keys: Option<Vec<KeyChord>> =
+                    sequence.split_whitespace().map(KeyChord::parse).collect();
+                match keys {
+                    Some(keys) if !keys.is_empty() => bindings.push(Binding { keys, action }),
+                    _ => problems.push(format!(
+                        "'{}' for {} is not a key",
+                        sequence,
+                        action.label()
+                    )),
+                }
+            }
+        }
+
+        let keymap = Keymap { bindings };
+        for context in [Context::Browser, Context::Form] {
+            problems.extend(keymap.conflicts(context));
+        }
+        if !problems.is_empty() {
+            bail!(
+                "Invalid key bindings in config:\n  {}",
+                problems.join("\n  ")
+            );
+        }
+        Ok(keymap)
+    }
+
+    fn active(&self, context: Context) -> impl Iterator<Item = &Binding> {
+        self.bindings.iter().filter(move |binding| {
+            binding.action.is_available(context)
+                && !(context.is_typing() && binding.keys[0].is_text())
+        })
+    }
+
+    /// Keys bound to two actions, or that start a longer binding of another
+    /// action and so would stop it from ever being reached.
+    fn conflicts(&self, context: Context) -> Vec<String> {
+        let active: Vec<&Binding> = self.active(context).collect();
+        let mut conflicts = Vec::new();
+
+        for (idx, a) in active.iter().enumerate() {
+            for b in &active[idx + 1..] {
+                if a.action == b.action {
+                    continue;
+                }
+                let (short, long) = if a.keys.len() <= b.keys.len() {
+                    (a, b)
+                } else {
+                    (b, a)
+                };
+                if long.keys.starts_with(&short.keys) {
+                    let problem = if short.keys.len() == long.keys.len() {
+                        format!(
+                            "'{}' is bound to both {} and {}",
+                            format_keys(&short.keys),
+                            short.action.label(),
+                            long.action.label()
+                        )
+                    } else {
+                        format!(
+                            "'{}' for {} hides '{}' for {}",
+                            format_keys(&short.keys),
+                            short.action.label(),
+                            format_keys(&long.keys),
+                            long.action.label()
+                        )
+                    };
+                    if !conflicts.contains(&problem) {
+                        conflicts.push(problem);
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Resolves the keys pressed so far.
+    pub fn lookup(&self, keys: &[KeyChord], context: Context) -> Lookup {
+        let mut pending = false;
+        for binding in self.active(context) {
+            if binding.keys == keys {
+                return Lookup::Action(binding.action);
+            }
+            pending |= binding.keys.starts_with(keys);
+        }
+        if pending {
+            Lookup::Pending
+        } else {
+            Lookup::None
+        }
+    }
+
+    /// Every key sequence bound to `action`, like `g g`.
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.action == action)
+            .map(|binding| format_keys(&binding.keys))
+            .collect()
+    }
+
+    /// The first key bound to `action` that works in `context`, for hints.
+    pub fn hint(&self, action: Action, context: Context) -> String {
+        self.active(context)
+            .find(|binding| binding.action == action)
+            .map_or(String::new(), |binding| format_keys(&binding.keys))
+    }
+}
@@ -1,52 +1,252 @@
-use std::sync::LazyLock;
+use crate::config::{IconSet, LanguageConfig};
+use crate::theme;
+use ratatui::style::Color;
+use std::sync::OnceLock;
+use unicode_width::UnicodeWidthStr;
 
+/// Icon for projects whose language has none.
+fn default_icon(icons: IconSet) -> &'static str {
+    match icons {
+        IconSet::Nerd => "󰄛",
+        IconSet::Unicode => "◇",
+        IconSet::Ascii => "[??]",
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Language {
-    pub names: Vec<&'static str>,
-    pub icon: &'static str,
-}
-
-pub static LANGUAGES: LazyLock<[Language; 7]> = LazyLock::new(|| {
-    [
-        Language {
-            names: vec!["C"],
-            icon: "",
-        },
-        Language {
-            names: vec!["C++", "CPP"],
-            icon: "󰙲",
-        },
-        Language {
-            names: vec!["C#"],
-            icon: "",
-        },
-        Language {
-            names: vec!["Typescript", "TS"],
-            icon: "󰛦",
-        },
-        Language {
-            names: vec!["Javascript", "JS"],
-            icon: "",
-        },
-        Language {
-            names: vec!["Go"],
-            icon: "󰟓",
-        },
-        Language {
-            names: vec!["Rust"],
-            icon: "󱘗",
-        },
-    ]
-});
+    /// The first name is the one shown, the rest are aliases
+    pub names: Vec<String>,
+    pub icon: String,
+    pub color: Option<Color>,
+    /// Extensions of source files, without the dot
+    pub extensions: Vec<String>,
+}
+
+/// A built-in language, with an icon for each icon set.
+struct Builtin {
+    names: &'static [&'static str],
+    nerd: &'static str,
+    unicode: &'static str,
+    ascii: &'static str,
+    color: &'static str,
+    extensions: &'static [&'static str],
+}
+
+const BUILTIN: [Builtin; 7] = [
+    Builtin {
+        names: &["C"],
+        nerd: "",
+        unicode: "Ⓒ",
+        ascii: "[c]",
+        color: "#555555",
+        extensions: &["c", "h"],
+    },
+    Builtin {
+        names: &["C++", "CPP"],
+        nerd: "󰙲",
+        unicode: "⊕",
+        ascii: "[c++]",
+        color: "#f34b7d",
+        extensions: &["cpp", "cc", "cxx", "hpp", "hh"],
+    },
+    Builtin {
+        names: &["C#"],
+        nerd: "",
+        unicode: "♯",
+        ascii: "[c#]",
+        color: "#178600",
+        extensions: &["cs"],
+    },
+    Builtin {
+        names: &["Typescript", "TS"],
+        nerd: "󰛦",
+        unicode: "Ⓣ",
+        ascii: "[ts]",
+        color: "#3178c6",
+        extensions: &["ts", "tsx", "mts", "cts"],
+    },
+    Builtin {
+        names: &["Javascript", "JS"],
+        nerd: "",
+        unicode: "Ⓙ",
+        ascii: "[js]",
+        color: "#f1e05a",
+        extensions: &["js", "jsx", "mjs", "cjs"],
+    },
+    Builtin {
+        names: &["Go"],
+        nerd: "󰟓",
+        unicode: "Ⓖ",
+        ascii: "[go]",
+        color: "#00add8",
+        extensions: &["go"],
+    },
+    Builtin {
+        names: &["Rust"],
+        nerd: "󱘗",
+        unicode: "Ⓡ",
+        ascii: "[rs]",
+        color: "#dea584",
+        extensions: &["rs"],
+    },
+];
+
+fn builtin(icons: IconSet) -> Vec<Language> {
+    BUILTIN
+        .iter()
+        .map(|lang| Language {
+            names: lang.names.iter().map(|name| name.to_string()).collect(),
+            icon: match icons {
+                IconSet::Nerd => lang.nerd,
+                IconSet::Unicode => lang.unicode,
+                IconSet::Ascii => lang.ascii,
+            }
+            .to_string(),
+            color: theme::parse_color(lang.color),
+            extensions: lang.extensions.iter().map(|ext| ext.to_string()).collect(),
+        })
+        .collect()
+}
+
+/// Short code like `[py]` for a language without an ASCII icon.
+fn ascii_code(name: &str) -> String {
+    let code: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(2)
+        .collect();
+    format!("[{}]", code.to_lowercase())
+}
+
+static LANGUAGES: OnceLock<Vec<Language>> = OnceLock::new();
+static ICONS: OnceLock<IconSet> = OnceLock::new();
+
+fn parse_color(language: &str, color: &str) -> Option<Color> {
+    let parsed = theme::parse_color(color);
+    if parsed.is_none() {
+        eprintln!("Ignoring unknown colour '{}' for {}", color, language);
+    }
+    parsed
+}
+
+/// Merges the languages from the config into the built-in ones. A configured
+/// language sharing a name with a built-in one overrides its icon and colour
+/// and adds its aliases, anything else is added as a new language. Icons come
+/// from the `icons` set and colours are dropped when `NO_COLOR` is set.
+///
+/// Has no effect once languages have been looked up.
+pub fn init(custom: &[LanguageConfig], icons: IconSet) {
+    let _ = ICONS.set(icons);
+    let mut languages = builtin(icons);
+
+    for config in custom {
+        let Some(name) = config.names.first() else {
+            eprintln!("Ignoring a language without any names");
+            continue;
+        };
+        let color = config
+            .color
+            .as_deref()
+            .and_then(|color| parse_color(name, color));
+        let icon = match icons {
+            IconSet::Nerd => config.icon.clone(),
+            IconSet::Unicode => config.unicode_icon.clone(),
+            IconSet::Ascii => config.ascii_icon.clone(),
+        };
+
+        let existing = languages.iter_mut().find(|lang| {
+            lang.names
+                .iter()
+                .any(|known| config.names.iter().any(|n| n.eq_ignore_ascii_case(known)))
+        });
+        match existing {
+            Some(lang) => {
+                for alias in &config.names {
+                    if !lang.names.iter().any(|n| n.eq_ignore_ascii_case(alias)) {
+                        lang.names.push(alias.clone());
+                    }
+                }
+                if let Some(icon) = icon {
+                    lang.icon = icon;
+                }
+                if color.is_some() {
+                    lang.color = color;
+                }
+                for ext in &config.extensions {
+                    if !lang.extensions.contains(ext) {
+                        lang.extensions.push(ext.clone());
+                    }
+                }
+            }
+            None => languages.push(Language {
+                names: config.names.clone(),
+                icon: icon.unwrap_or_else(|| match icons {
+                    IconSet::Ascii => ascii_code(name),
+                    _ => default_icon(icons).to_string(),
+                }),
+                color,
+                extensions: config.extensions.clone(),
+            }),
+        }
+    }
+
+    if theme::no_color() {
+        for lang in &mut languages {
+            lang.color = None;
+        }
+    }
+
+    let _ = LANGUAGES.set(languages);
+}
+
+fn icon_set() -> IconSet {
+    ICONS.get().copied().unwrap_or_default()
+}
+
+/// Every known language, built-in or configured.
+pub fn all() -> &'static [Language] {
+    LANGUAGES.get_or_init(|| builtin(icon_set()))
+}
+
+/// Icon for a language name, or the generic one if the language is unknown.
+pub fn icon_for(name: &str) -> &'static str {
+    Language::from_name(name).map_or(default_icon(icon_set()), |lang| lang.icon.as_str())
+}
+
+/// Colour for a language name's icon, if it has one.
+pub fn color_for(name: &str) -> Option<Color> {
+    Language::from_name(name).and_then(|lang| lang.color)
+}
+
+/// Columns the widest icon of the icon set takes up.
+pub fn icon_width() -> usize {
+    all()
+        .iter()
+        .map(|lang| lang.icon.as_str())
+        .chain([default_icon(icon_set())])
+        .map(UnicodeWidthStr::width)
+        .max()
+        .unwrap_or(0)
+        // Nerd Font glyphs report a width of one but draw over two columns
+        .max(2)
+}
 
 impl Language {
     pub fn from_name(name: &str) -> Option<&'static Language> {
-        LANGUAGES.iter().find(|lang| {
-            let lower_names = lang
-                .names
-                .iter()
-                .map(|name| name.to_lowercase())
-                .collect::<Vec<String>>();
-            lower_names.contains(&name.to_lowercase())
-        })
+        all()
+            .iter()
+            .find(|lang| lang.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.names[0]
+    }
+
+    /// The language source files with extension `ext` are written in.
+    pub fn from_extension(ext: &str) -> Option<&'static Language> {
+        all()
+            .iter()
+            .find(|lang| lang.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
     }
 }
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Windows and panes to create when a project is opened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Layout {
+    pub windows: Vec<WindowLayout>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowLayout {
+    /// Defaults to the project name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Working directory, relative to the project directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub panes: Vec<PaneLayout>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PaneLayout {
+    /// How this pane is split off the previous one. Ignored for the first pane.
+    #[serde(default, skip_serializing_if = "SplitDirection::is_default")]
+    pub split: SplitDirection,
+    /// Size of the new pane, either a percentage (`"10%"`) or lines/columns (`"20"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+    /// Working directory, relative to the window's root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    /// Command typed into the pane once it's created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Whether this pane is active once the layout is built
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub focus: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitDirection {
+    /// New pane below the previous one
+    #[default]
+    Vertical,
+    /// New pane to the right of the previous one
+    Horizontal,
+}
+
+impl SplitDirection {
+    fn is_default(&self) -> bool {
+        *self == SplitDirection::default()
+    }
+}
+
+impl Default for Layout {
+    /// nvim on top with a small shell underneath.
+    fn default() -> Self {
+        Layout::builtin("nvim")
+    }
+}
+
+impl Layout {
+    /// `editor` on top with a small shell underneath.
+    pub fn builtin(editor: &str) -> Self {
+        Layout {
+            windows: vec![WindowLayout {
+                name: None,
+                root: None,
+                panes: vec![
+                    PaneLayout {
+                        command: Some(format!("{} .", editor)),
+                        focus: true,
+                        ..Default::default()
+                    },
+                    PaneLayout {
+                        split: SplitDirection::Vertical,
+                        size: Some("10%".to_string()),
+                        ..Default::default()
+                    },
+                ],
+            }],
+        }
+    }
+
+    /// Picks the project's own layout, then the configured default, then the
+    /// built-in one with the project's editor.
+    pub fn resolve(
+        project: Option<&Layout>,
+        default: Option<&Layout>,
+        editor: Option<&str>,
+    ) -> Layout {
+        project
+            .or(default)
+            .filter(|layout| !layout.windows.is_empty())
+            .cloned()
+            .unwrap_or_else(|| editor.map_or_else(Layout::default, Layout::builtin))
+    }
+}
+
+/// Resolves a layout `root` against the directory it is relative to.
+pub fn resolve_root(base: &str, root: Option<&str>) -> String {
+    match root {
+        Some(root) => Path::new(base).join(root).to_string_lossy().to_string(),
+        None => base.to_string(),
+    }
+}
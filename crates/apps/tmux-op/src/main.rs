@@ -1,69 +1,104 @@
+mod cache;
+mod cli;
 mod config;
+mod git;
+mod history;
+mod keys;
 mod languages;
+mod layout;
+mod multiplexer;
 mod new_project;
+mod preview;
 mod project_finder;
+mod query;
+mod search;
+mod suggest;
+mod template;
+mod theme;
 mod ui;
 
-use std::{env, time::Instant};
+use std::{sync::mpsc, thread, time::Instant};
 
+use cache::ProjectIndex;
+use clap::Parser;
+use cli::{Cli, Command};
 use config::Config;
+use history::History;
+use keys::Keymap;
 use new_project::create_project;
-use project_finder::find_project_files;
+use project_finder::{find_project_files, scan_projects};
 
-fn print_help() {
-    println!("Usage: tmux-op [command]");
-    println!();
-    println!("Commands:");
-    println!("  mk    Create a new project");
-    println!();
-    println!("Options:");
-    println!("  --debug    Print debug information");
+fn scan(config: &Config, debug: bool) -> anyhow::Result<()> {
+    let search_start = Instant::now();
+    let projects = find_project_files(&config.search_paths, &config.detection_rules(), None)?;
+    ProjectIndex::from_projects(&projects).save()?;
+
+    if debug {
+        eprintln!(
+            "Project search took: {}ms",
+            search_start.elapsed().as_millis()
+        );
+    }
+
+    println!("Indexed {} projects", projects.len());
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
     dirs::home_dir().expect("Failed to get home directory");
-    let args: Vec<String> = env::args().collect();
-    let debug = args.iter().any(|arg| arg == "--debug");
+    let cli = Cli::parse();
+    let debug = cli.debug;
 
-    if args.len() > 1 {
-        match args[1].as_str() {
-            "mk" => {
-                return create_project();
-            }
-            "help" => {
-                print_help();
-                std::process::exit(0);
-            }
-            "--help" => {
-                print_help();
-                std::process::exit(0);
-            }
-            "--debug" => {
-                // Skip the debug argument
-            }
-            _ => {
-                print_help();
-                std::process::exit(1);
-            }
+    match cli.command {
+        Some(Command::Completions { shell }) => {
+            cli::completions(shell);
+            return Ok(());
         }
+        Some(Command::History { action }) => return cli::history(action),
+        _ => {}
     }
 
     let config_start = Instant::now();
-    let config = Config::load()?;
+    let config = Config::load(cli.config.as_deref())?;
     let config_duration = config_start.elapsed();
+    languages::init(&config.languages, config.icons);
+    let keymap = Keymap::new(&config.keys)?;
 
     if debug {
         eprintln!("Config load took: {}ms", config_duration.as_millis());
+        for path in &config.search_paths {
+            eprintln!("Searching in: {}", path.display());
+        }
     }
 
-    let search_start = Instant::now();
-    let projects = find_project_files(&config.search_paths)?;
-    let search_duration = search_start.elapsed();
-
-    if debug {
-        eprintln!("Project search took: {}ms", search_duration.as_millis());
+    match cli.command {
+        Some(Command::Mk(args)) => return create_project(&config, &keymap, &args),
+        Some(Command::Scan) => return scan(&config, debug),
+        Some(Command::List {
+            json,
+            format,
+            cached,
+        }) => return cli::list(&config, json, &format, cached),
+        Some(Command::Open { query, first }) => return cli::open(&config, &query, first),
+        Some(Command::Path { query, first }) => return cli::path(&config, &query, first),
+        _ => {}
     }
+    // Show the cached projects straight away and stream in whatever the scan finds
+    let index = ProjectIndex::load();
+    let cached = index.projects();
+    let (sender, receiver) = mpsc::channel();
+    let search_paths = config.search_paths.clone();
+    let detection = config.detection_rules();
+    // Exiting doesn't wait for the scan. If it's cut short the index from the
+    // last full scan is kept, since the save only replaces it once written
+    thread::spawn(move || {
+        let projects = scan_projects(&search_paths, &detection, Some(&index), &sender);
+        let _ = ProjectIndex::from_projects(&projects).save();
+    });
 
-    let _ = ui::main(projects);
+    let picked = ui::main(&config, keymap, cached, receiver, History::load().scores())?;
+    if let Some((project, mode)) = picked {
+        cli::open_project(&config, &project, mode)?;
+    }
     Ok(())
 }
@@ -0,0 +1,235 @@
+mod shell;
+mod tmux;
+mod zellij;
+
+use crate::config::{MultiplexerKind, OpenMode};
+use crate::layout::{resolve_root, Layout, SplitDirection};
+use crate::project_finder::ProjectInfo;
+use shell::Shell;
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+use std::path::Path;
+use tmux::Tmux;
+use zellij::Zellij;
+
+/// A terminal multiplexer that projects can be opened in.
+///
+/// Panes are identified by whatever string the backend hands back from
+/// `create_session`, `new_window` and `split`, which also export `env` into
+/// the pane they create.
+pub trait Multiplexer {
+    /// Whether tmux-op is running inside this multiplexer.
+    fn is_inside(&self) -> bool;
+
+    /// Whether `switch` can move the current client to another session.
+    fn can_switch_sessions(&self) -> bool {
+        true
+    }
+
+    fn list_sessions(&self) -> io::Result<Vec<String>>;
+
+    /// Directory `session` was started in, if the backend can tell.
+    fn session_path(&self, _session: &str) -> Option<String> {
+        None
+    }
+
+    /// Id of the pane tmux-op is running in, for splitting it.
+    fn current_pane(&self) -> String;
+
+    /// Creates a detached session and returns the id of its first pane.
+    fn create_session(
+        &self,
+        session: &str,
+        window: &str,
+        root: &str,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String>;
+
+    /// Adds a window to `session`, or to the current session if it is `None`,
+    /// and returns the id of its first pane.
+    fn new_window(
+        &self,
+        session: Option<&str>,
+        window: &str,
+        root: &str,
+        background: bool,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String>;
+
+    /// Splits `pane` and returns the id of the new pane.
+    fn split(
+        &self,
+        pane: &str,
+        direction: SplitDirection,
+        size: Option<&str>,
+        root: &str,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String>;
+
+    fn send_command(&self, pane: &str, command: &str) -> io::Result<()>;
+
+    fn focus(&self, pane: &str) -> io::Result<()>;
+
+    /// Moves the user into `session`, attaching to it if needed.
+    fn switch(&self, session: &str) -> io::Result<()>;
+}
+
+/// Picks the backend from config, or from the environment tmux-op runs in.
+pub fn detect(kind: MultiplexerKind) -> Box<dyn Multiplexer> {
+    let is_set = |var: &str| env::var_os(var).is_some_and(|v| !v.is_empty());
+
+    match kind {
+        MultiplexerKind::Tmux => Box::new(Tmux),
+        MultiplexerKind::Zellij => Box::new(Zellij),
+        MultiplexerKind::None => Box::new(Shell::default()),
+        MultiplexerKind::Auto if is_set("TMUX") => Box::new(Tmux),
+        MultiplexerKind::Auto if is_set("ZELLIJ") => Box::new(Zellij),
+        MultiplexerKind::Auto => Box::new(Shell::default()),
+    }
+}
+
+/// Turns a project name into something multiplexers accept as a session name.
+/// tmux silently rewrites `.` and `:`, which would stop us finding the
+/// session again, so replace them (and whitespace) up front.
+pub fn session_name(name: &str) -> String {
+    let sanitised: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '.' | ':' => '_',
+            c if c.is_whitespace() => '_',
+            c => c,
+        })
+        .collect();
+
+    if sanitised.is_empty() {
+        "project".to_string()
+    } else {
+        sanitised
+    }
+}
+
+/// Session `project` opens in: one named after it, or after it and its parent
+/// directories while that name is taken by a session of another project with
+/// the same name.
+fn project_session(mux: &dyn Multiplexer, project: &ProjectInfo) -> io::Result<String> {
+    let sessions = mux.list_sessions()?;
+    let directory = project.open_directory();
+    let mut parents = Path::new(&project.directory)
+        .ancestors()
+        .skip(1)
+        .filter_map(|parent| parent.file_name())
+        .map(|parent| parent.to_string_lossy().to_string());
+
+    let mut name = project.name.clone();
+    loop {
+        let session = session_name(&name);
+        if !sessions.contains(&session) {
+            return Ok(session);
+        }
+        // Backends that can't tell where a session is just reuse it
+        match mux.session_path(&session) {
+            Some(path) if !Path::new(&path).starts_with(&directory) => {}
+            _ => return Ok(session),
+        }
+        match parents.next() {
+            Some(parent) => name = format!("{}-{}", name, parent),
+            None => return Ok(session),
+        }
+    }
+}
+
+/// Creates every window and pane in `layout`, either in a new `session` or,
+/// if that is `None`, in the current one.
+fn build_layout(
+    mux: &dyn Multiplexer,
+    project: &ProjectInfo,
+    layout: &Layout,
+    session: Option<&str>,
+) -> io::Result<()> {
+    let mut focus: Option<String> = None;
+    let directory = project.open_directory();
+    let env = project.pane_env();
+
+    for (window_idx, window) in layout.windows.iter().enumerate() {
+        let window_root = resolve_root(&directory, window.root.as_deref());
+        let window_name = window
+            .name
+            .clone()
+            .unwrap_or_else(|| session_name(&project.name));
+
+        let first_root = resolve_root(
+            &window_root,
+            window.panes.first().and_then(|p| p.root.as_deref()),
+        );
+        let mut pane_id = match session {
+            Some(session) if window_idx == 0 => {
+                mux.create_session(session, &window_name, &first_root, &env)?
+            }
+            // Only the first window takes focus, the rest open in the background
+            _ => mux.new_window(session, &window_name, &first_root, window_idx > 0, &env)?,
+        };
+
+        for (pane_idx, pane) in window.panes.iter().enumerate() {
+            if pane_idx > 0 {
+                let root = resolve_root(&window_root, pane.root.as_deref());
+                pane_id = mux.split(&pane_id, pane.split, pane.size.as_deref(), &root, &env)?;
+            }
+
+            if let Some(command) = &pane.command {
+                mux.send_command(&pane_id, command)?;
+            }
+            if pane.focus && focus.is_none() {
+                focus = Some(pane_id.clone());
+            }
+        }
+    }
+
+    if let Some(pane_id) = focus {
+        mux.focus(&pane_id)?;
+    }
+    Ok(())
+}
+
+/// Opens `project` using its own layout, falling back to `default_layout`.
+pub fn open_project(
+    mux: &dyn Multiplexer,
+    project: &ProjectInfo,
+    mode: OpenMode,
+    default_layout: Option<&Layout>,
+) -> io::Result<()> {
+    // A split only holds one pane, so the layout doesn't apply
+    if mode == OpenMode::Split && mux.is_inside() {
+        let pane = mux.split(
+            &mux.current_pane(),
+            SplitDirection::Horizontal,
+            None,
+            &project.open_directory(),
+            &project.pane_env(),
+        )?;
+        return mux.focus(&pane);
+    }
+
+    let layout = Layout::resolve(
+        project.layout.as_ref(),
+        default_layout,
+        project.editor.as_deref(),
+    );
+
+    // A window needs a session to live in, and some backends can't hop
+    // between sessions from inside one
+    let use_window = match mode {
+        OpenMode::Window | OpenMode::Split => mux.is_inside(),
+        OpenMode::Session => mux.is_inside() && !mux.can_switch_sessions(),
+    };
+    if use_window {
+        return build_layout(mux, project, &layout, None);
+    }
+
+    let name = project_session(mux, project)?;
+    if !mux.list_sessions()?.contains(&name) {
+        build_layout(mux, project, &layout, Some(&name))?;
+    }
+    mux.switch(&name)
+}
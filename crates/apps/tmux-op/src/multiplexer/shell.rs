@@ -0,0 +1,120 @@
+use super::Multiplexer;
+use crate::layout::SplitDirection;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+use std::process::Command;
+
+/// Fallback for when there is no multiplexer: only one pane can exist, so the
+/// layout is recorded and `switch` starts the focused pane's command (or just
+/// `$SHELL`) in its directory.
+#[derive(Default)]
+pub struct Shell {
+    panes: RefCell<Vec<ShellPane>>,
+    focused: RefCell<Option<usize>>,
+}
+
+struct ShellPane {
+    root: String,
+    env: BTreeMap<String, String>,
+    command: Option<String>,
+}
+
+impl Shell {
+    fn add_pane(&self, root: &str, env: &BTreeMap<String, String>) -> String {
+        let mut panes = self.panes.borrow_mut();
+        panes.push(ShellPane {
+            root: root.to_string(),
+            env: env.clone(),
+            command: None,
+        });
+        (panes.len() - 1).to_string()
+    }
+
+    fn pane_index(pane: &str) -> io::Result<usize> {
+        pane.parse()
+            .map_err(|_| io::Error::other(format!("Unknown pane {}", pane)))
+    }
+}
+
+impl Multiplexer for Shell {
+    fn is_inside(&self) -> bool {
+        false
+    }
+
+    fn list_sessions(&self) -> io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Never inside a multiplexer, so there is no pane to split.
+    fn current_pane(&self) -> String {
+        String::new()
+    }
+
+    fn create_session(
+        &self,
+        _session: &str,
+        _window: &str,
+        root: &str,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String> {
+        Ok(self.add_pane(root, env))
+    }
+
+    fn new_window(
+        &self,
+        _session: Option<&str>,
+        _window: &str,
+        root: &str,
+        _background: bool,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String> {
+        Ok(self.add_pane(root, env))
+    }
+
+    fn split(
+        &self,
+        _pane: &str,
+        _direction: SplitDirection,
+        _size: Option<&str>,
+        root: &str,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String> {
+        Ok(self.add_pane(root, env))
+    }
+
+    fn send_command(&self, pane: &str, command: &str) -> io::Result<()> {
+        let idx = Self::pane_index(pane)?;
+        if let Some(pane) = self.panes.borrow_mut().get_mut(idx) {
+            pane.command = Some(command.to_string());
+        }
+        Ok(())
+    }
+
+    fn focus(&self, pane: &str) -> io::Result<()> {
+        *self.focused.borrow_mut() = Some(Self::pane_index(pane)?);
+        Ok(())
+    }
+
+    fn switch(&self, _session: &str) -> io::Result<()> {
+        let panes = self.panes.borrow();
+        let idx = self.focused.borrow().unwrap_or(0);
+        let Some(pane) = panes.get(idx) else {
+            return Ok(());
+        };
+
+        let shell = env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+        let mut command = Command::new(&shell);
+        command.current_dir(&pane.root).envs(&pane.env);
+        // Drop back into a shell in the project once the command exits
+        if let Some(startup) = &pane.command {
+            command
+                .arg("-c")
+                .arg(format!("{}; exec {}", startup, shell));
+        }
+
+        command.status()?;
+        Ok(())
+    }
+}
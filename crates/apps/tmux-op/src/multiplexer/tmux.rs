@@ -0,0 +1,161 @@
+use super::Multiplexer;
+use crate::layout::SplitDirection;
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+use std::process::{Command, Stdio};
+
+pub struct Tmux;
+
+/// Runs a tmux command, treating a non-zero exit as an error.
+fn tmux_checked(args: &[&str]) -> io::Result<()> {
+    if Command::new("tmux").args(args).status()?.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("tmux {} failed", args.join(" "))))
+    }
+}
+
+/// Runs a tmux command that prints something, e.g. with `-P -F`.
+fn tmux_output(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("tmux")
+        .args(args)
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("tmux {} failed", args.join(" "))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `-e NAME=value` for each variable, as taken by the commands creating panes.
+fn env_args(env: &BTreeMap<String, String>) -> Vec<String> {
+    env.iter()
+        .flat_map(|(name, value)| ["-e".to_string(), format!("{}={}", name, value)])
+        .collect()
+}
+
+impl Multiplexer for Tmux {
+    fn is_inside(&self) -> bool {
+        env::var_os("TMUX").is_some_and(|v| !v.is_empty())
+    }
+
+    fn list_sessions(&self) -> io::Result<Vec<String>> {
+        // Fails when no server is running, which just means there are no sessions
+        let output = Command::new("tmux")
+            .args(["list-sessions", "-F", "#{session_name}"])
+            .stderr(Stdio::null())
+            .output()?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect())
+    }
+
+    fn session_path(&self, session: &str) -> Option<String> {
+        let target = format!("={}:", session);
+        tmux_output(&["display-message", "-p", "-t", &target, "#{session_path}"]).ok()
+    }
+
+    fn current_pane(&self) -> String {
+        env::var("TMUX_PANE").unwrap_or_default()
+    }
+
+    fn create_session(
+        &self,
+        session: &str,
+        window: &str,
+        root: &str,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String> {
+        let env = env_args(env);
+        let mut args = vec![
+            "new-session",
+            "-d",
+            "-P",
+            "-F",
+            "#{pane_id}",
+            "-s",
+            session,
+            "-n",
+            window,
+            "-c",
+            root,
+        ];
+        args.extend(env.iter().map(String::as_str));
+        tmux_output(&args)
+    }
+
+    fn new_window(
+        &self,
+        session: Option<&str>,
+        window: &str,
+        root: &str,
+        background: bool,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String> {
+        let mut args = vec!["new-window", "-P", "-F", "#{pane_id}"];
+        if background {
+            args.push("-d");
+        }
+
+        let target = session.map(|session| format!("={}:", session));
+        if let Some(target) = &target {
+            args.extend(["-t", target]);
+        }
+
+        args.extend(["-n", window, "-c", root]);
+        let env = env_args(env);
+        args.extend(env.iter().map(String::as_str));
+        tmux_output(&args)
+    }
+
+    fn split(
+        &self,
+        pane: &str,
+        direction: SplitDirection,
+        size: Option<&str>,
+        root: &str,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String> {
+        let direction = match direction {
+            SplitDirection::Vertical => "-v",
+            SplitDirection::Horizontal => "-h",
+        };
+        let mut args = vec![
+            "split-window",
+            direction,
+            "-P",
+            "-F",
+            "#{pane_id}",
+            "-t",
+            pane,
+            "-c",
+            root,
+        ];
+        if let Some(size) = size {
+            args.extend(["-l", size]);
+        }
+        let env = env_args(env);
+        args.extend(env.iter().map(String::as_str));
+        tmux_output(&args)
+    }
+
+    fn send_command(&self, pane: &str, command: &str) -> io::Result<()> {
+        tmux_checked(&["send-keys", "-t", pane, command, "C-m"])
+    }
+
+    fn focus(&self, pane: &str) -> io::Result<()> {
+        tmux_checked(&["select-window", "-t", pane])?;
+        tmux_checked(&["select-pane", "-t", pane])
+    }
+
+    fn switch(&self, session: &str) -> io::Result<()> {
+        let target = format!("={}", session);
+        if self.is_inside() {
+            tmux_checked(&["switch-client", "-t", &target])
+        } else {
+            tmux_checked(&["attach-session", "-t", &target])
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use super::Multiplexer;
+use crate::layout::SplitDirection;
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Zellij has no pane ids on the command line, so every action applies to the
+/// focused pane of a session. Pane ids are just the session name (empty for
+/// the current session), which works because layouts are built one pane at a
+/// time and each new pane takes focus.
+pub struct Zellij;
+
+/// Runs a `zellij action` against `session`, or the current session if empty.
+fn action(session: &str, args: &[&str]) -> io::Result<()> {
+    let mut command = Command::new("zellij");
+    if !session.is_empty() {
+        command.args(["--session", session]);
+    }
+    let status = command.arg("action").args(args).status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "zellij action {} failed",
+            args.join(" ")
+        )))
+    }
+}
+
+/// Types an `export` for each variable into the focused pane of `session`,
+/// since zellij can only pass variables on to a session when creating it.
+fn export_env(session: &str, env: &BTreeMap<String, String>) -> io::Result<()> {
+    for (name, value) in env {
+        let value = value.replace('\'', "'\\''");
+        action(
+            session,
+            &["write-chars", &format!("export {}='{}'\n", name, value)],
+        )?;
+    }
+    Ok(())
+}
+
+impl Multiplexer for Zellij {
+    fn is_inside(&self) -> bool {
+        env::var_os("ZELLIJ").is_some()
+    }
+
+    /// The zellij CLI can only attach from outside a session.
+    fn can_switch_sessions(&self) -> bool {
+        !self.is_inside()
+    }
+
+    fn list_sessions(&self) -> io::Result<Vec<String>> {
+        let output = Command::new("zellij")
+            .args(["list-sessions", "--short", "--no-formatting"])
+            .stderr(Stdio::null())
+            .output()?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect())
+    }
+
+    /// The current session's focused pane, see the note on [`Zellij`].
+    fn current_pane(&self) -> String {
+        String::new()
+    }
+
+    fn create_session(
+        &self,
+        session: &str,
+        window: &str,
+        root: &str,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String> {
+        // The session's server and every pane in it inherit the variables
+        let status = Command::new("zellij")
+            .args(["attach", "--create-background", session])
+            .current_dir(root)
+            .envs(env)
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "zellij failed to create session {}",
+                session
+            )));
+        }
+
+        action(session, &["rename-tab", window])?;
+        Ok(session.to_string())
+    }
+
+    fn new_window(
+        &self,
+        session: Option<&str>,
+        window: &str,
+        root: &str,
+        _background: bool,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String> {
+        let session = session.unwrap_or_default();
+        action(session, &["new-tab", "--name", window, "--cwd", root])?;
+        export_env(session, env)?;
+        Ok(session.to_string())
+    }
+
+    /// Zellij picks pane sizes itself, so `size` is ignored.
+    fn split(
+        &self,
+        pane: &str,
+        direction: SplitDirection,
+        _size: Option<&str>,
+        root: &str,
+        env: &BTreeMap<String, String>,
+    ) -> io::Result<String> {
+        let direction = match direction {
+            SplitDirection::Vertical => "down",
+            SplitDirection::Horizontal => "right",
+        };
+        action(pane, &["new-pane", "--direction", direction, "--cwd", root])?;
+        export_env(pane, env)?;
+        Ok(pane.to_string())
+    }
+
+    fn send_command(&self, pane: &str, command: &str) -> io::Result<()> {
+        action(pane, &["write-chars", &format!("{}\n", command)])
+    }
+
+    /// Panes can't be addressed individually, so whichever pane was created
+    /// last keeps focus.
+    fn focus(&self, _pane: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn switch(&self, session: &str) -> io::Result<()> {
+        let status = Command::new("zellij").args(["attach", session]).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "zellij failed to attach to {}",
+                session
+            )))
+        }
+    }
+}
@@ -1,113 +1,447 @@
-use anyhow::{Context, Result};
-use crossterm::event::{self, KeyCode};
+use crate::cli::MkArgs;
+use crate::config::Config;
+use crate::keys::{Action, Context as KeyContext, KeyChord, Keymap, Lookup};
+use crate::languages::Language;
+use crate::project_finder::ProjectConfig;
+use crate::suggest::{suggest_languages, Suggestion};
+use crate::template::{Placeholders, Template};
+use crate::theme::Theme;
+use anyhow::{bail, Context, Result};
+use crossterm::event::{self, KeyCode, KeyEvent};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
     DefaultTerminal,
 };
-use serde_json::json;
-use std::{fs, io, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Width of the labels in the details block.
+const LABEL_WIDTH: usize = 13;
 
 pub struct CreateApp {
     project_name: String,
+    /// The highlighted language, shown until something is typed
     selected_language: String,
+    /// Text the language list is filtered by
+    language_query: String,
     edit_mode: EditMode,
     languages: Vec<String>,
     filtered_languages: Vec<String>, // New: for showing filtered language options
+    /// Highlighted entry of `filtered_languages`
+    language_state: ListState,
+    /// Search roots a new directory can be created under, empty when the
+    /// project goes in the current directory
+    roots: Vec<PathBuf>,
+    root: usize,
+    /// Languages guessed from the directory's contents
+    suggestions: Vec<Suggestion>,
+    description: String,
+    /// Comma separated
+    tags: String,
+    editor: String,
+    /// `name=command` entries, see [`parse_entries`]
+    commands: String,
+    /// `NAME=value` entries, see [`parse_entries`]
+    env: String,
+    project_root: String,
+    hidden: bool,
+    /// Why the form couldn't be submitted
+    error: Option<String>,
+    /// The `.dexproject` being edited, holding whatever the form doesn't show
+    project: ProjectConfig,
 }
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum EditMode {
     Name,
+    Location,
     Language,
+    Description,
+    Tags,
+    Editor,
+    Commands,
+    Env,
+    Root,
+    Hidden,
 }
 
-impl CreateApp {
-    fn new() -> Self {
-        let current_dir = std::env::current_dir()
-            .ok()
-            .and_then(|p| p.file_name().map(|s| s.to_string_lossy().to_string()))
-            .unwrap_or_default();
+impl EditMode {
+    /// The fields of the details block, in order.
+    const DETAILS: [EditMode; 7] = [
+        EditMode::Description,
+        EditMode::Tags,
+        EditMode::Editor,
+        EditMode::Commands,
+        EditMode::Env,
+        EditMode::Root,
+        EditMode::Hidden,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            EditMode::Name => "Project Name",
+            EditMode::Location => "Location",
+            EditMode::Language => "Language",
+            EditMode::Description => "Description",
+            EditMode::Tags => "Tags",
+            EditMode::Editor => "Editor",
+            EditMode::Commands => "Commands",
+            EditMode::Env => "Environment",
+            EditMode::Root => "Root",
+            EditMode::Hidden => "Hidden",
+        }
+    }
+}
+
+/// Whether `name` can name a command or variable.
+fn is_entry_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Splits `name=value` into its trimmed name and value.
+fn parse_entry(entry: &str) -> Option<(String, String)> {
+    let (name, value) = entry.split_once('=')?;
+    let name = name.trim();
+    is_entry_name(name).then(|| (name.to_string(), value.trim().to_string()))
+}
+
+/// Parses `name=value` entries separated by `;`. A `;` only starts a new
+/// entry when `name=` follows it, so commands like `make; make install` stay
+/// whole.
+fn parse_entries(text: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut entries: Vec<String> = Vec::new();
+    for part in text.split(';').filter(|part| !part.trim().is_empty()) {
+        match entries.last_mut() {
+            Some(last) if parse_entry(part).is_none() => {
+                last.push(';');
+                last.push_str(part);
+            }
+            _ => entries.push(part.to_string()),
+        }
+    }
+    entries
+        .iter()
+        .map(|entry| {
+            parse_entry(entry)
+                .ok_or_else(|| format!("'{}' should look like name=value", entry.trim()))
+        })
+        .collect()
+}
 
-        // Get available languages plus "UNKNOWN"
-        let mut languages = crate::languages::LANGUAGES
+fn format_entries(entries: &BTreeMap<String, String>) -> String {
+    entries
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn parse_tags<S: AsRef<str>>(tags: &[S]) -> Vec<String> {
+    tags.iter()
+        .map(|tag| tag.as_ref().trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// Why `name` can't name a new project directory, if it can't: it has to be a
+/// single path component so the project stays under the chosen search path.
+fn dir_name_error(name: &str) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        Some("Project name can't be empty".to_string())
+    } else if name.contains(['/', '\\']) || name == "." || name == ".." {
+        Some(format!(
+            "Project name '{}' must be a single directory name",
+            name
+        ))
+    } else {
+        None
+    }
+}
+
+/// `None` for blank text, so clearing a field removes it from the file.
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// The end of `text` that fits in `width` columns, so what's being typed
+/// stays in view.
+fn tail(text: &str, width: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    chars[chars.len().saturating_sub(width)..].iter().collect()
+}
+
+impl CreateApp {
+    fn new(project: ProjectConfig, roots: Vec<PathBuf>, suggestions: Vec<Suggestion>) -> Self {
+        // Get available languages plus "UNKNOWN", suggested ones first
+        let mut languages = suggestions
             .iter()
-            .flat_map(|l| l.names.first().cloned())
-            .map(String::from)
+            .map(|s| s.language.clone())
             .collect::<Vec<_>>();
+        for lang in crate::languages::all() {
+            if !languages.iter().any(|l| l == lang.name()) {
+                languages.push(lang.name().to_string());
+            }
+        }
         languages.push("UNKNOWN".to_string());
 
+        // The file's language, else the likeliest one, is filled in without
+        // filtering out the alternatives, so it only takes Enter to accept it
+        let selected_language = project
+            .language
+            .as_deref()
+            .and_then(|language| language_name(language).ok())
+            .or_else(|| suggestions.first().map(|s| s.language.clone()))
+            .unwrap_or_default();
+        let highlighted = languages
+            .iter()
+            .position(|lang| *lang == selected_language)
+            .unwrap_or(0);
+
         Self {
-            project_name: current_dir,
-            selected_language: String::new(),
+            project_name: project.name.clone().unwrap_or_default(),
+            selected_language,
+            language_query: String::new(),
             edit_mode: EditMode::Name,
             filtered_languages: languages.clone(),
+            language_state: ListState::default().with_selected(Some(highlighted)),
             languages,
+            roots,
+            root: 0,
+            suggestions,
+            description: project.description.clone().unwrap_or_default(),
+            tags: project.tags.join(", "),
+            editor: project.editor.clone().unwrap_or_default(),
+            commands: format_entries(&project.commands),
+            env: format_entries(&project.env),
+            project_root: project.root.clone().unwrap_or_default(),
+            hidden: project.hidden,
+            error: None,
+            project,
+        }
+    }
+
+    fn highlighted_language(&self) -> Option<&String> {
+        self.language_state
+            .selected()
+            .and_then(|pos| self.filtered_languages.get(pos))
+    }
+
+    /// Moves the highlight by `delta` entries, stopping at either end.
+    fn move_highlight(&mut self, delta: isize) {
+        let Some(last) = self.filtered_languages.len().checked_sub(1) else {
+            return;
+        };
+        let pos = self.language_state.selected().unwrap_or(0);
+        self.language_state
+            .select(Some(pos.saturating_add_signed(delta).min(last)));
+        self.select_highlighted();
+    }
+
+    /// Makes the highlighted language the selected one, clearing the
+    /// selection when nothing matches the query.
+    fn select_highlighted(&mut self) {
+        self.selected_language = self.highlighted_language().cloned().unwrap_or_default();
+    }
+
+    fn next_mode(&self) -> EditMode {
+        match self.edit_mode {
+            EditMode::Name if self.roots.is_empty() => EditMode::Language,
+            EditMode::Name => EditMode::Location,
+            EditMode::Location => EditMode::Language,
+            EditMode::Language => EditMode::Description,
+            EditMode::Description => EditMode::Tags,
+            EditMode::Tags => EditMode::Editor,
+            EditMode::Editor => EditMode::Commands,
+            EditMode::Commands => EditMode::Env,
+            EditMode::Env => EditMode::Root,
+            EditMode::Root => EditMode::Hidden,
+            EditMode::Hidden => EditMode::Name,
+        }
+    }
+
+    /// Text typed into the focused field, `None` for fields that aren't
+    /// plain text.
+    fn text_field(&mut self) -> Option<&mut String> {
+        match self.edit_mode {
+            EditMode::Name => Some(&mut self.project_name),
+            EditMode::Description => Some(&mut self.description),
+            EditMode::Tags => Some(&mut self.tags),
+            EditMode::Editor => Some(&mut self.editor),
+            EditMode::Commands => Some(&mut self.commands),
+            EditMode::Env => Some(&mut self.env),
+            EditMode::Root => Some(&mut self.project_root),
+            EditMode::Location | EditMode::Language | EditMode::Hidden => None,
+        }
+    }
+
+    /// What a field of the details block shows.
+    fn detail(&self, mode: EditMode) -> String {
+        match mode {
+            EditMode::Description => self.description.clone(),
+            EditMode::Tags => self.tags.clone(),
+            EditMode::Editor => self.editor.clone(),
+            EditMode::Commands => self.commands.clone(),
+            EditMode::Env => self.env.clone(),
+            EditMode::Root => self.project_root.clone(),
+            EditMode::Hidden if self.hidden => "yes".to_string(),
+            _ => "no".to_string(),
         }
     }
 
     fn filter_languages(&mut self) {
-        let query = self.selected_language.to_lowercase();
+        let query = self.language_query.to_lowercase();
         self.filtered_languages = self
             .languages
             .iter()
             .filter(|lang| lang.to_lowercase().contains(&query))
             .cloned()
             .collect();
+
+        // Typing "C" should highlight C rather than C++ or C#, and clearing
+        // the query goes back to the selection
+        let exact = self.filtered_languages.iter().position(|lang| {
+            lang.to_lowercase() == query || (query.is_empty() && *lang == self.selected_language)
+        });
+        let first = (!self.filtered_languages.is_empty()).then_some(0);
+        self.language_state = ListState::default().with_selected(exact.or(first));
+        self.select_highlighted();
     }
 
-    fn handle_input(&mut self, key: KeyCode) -> bool {
-        match (self.edit_mode.clone(), key) {
-            (_, KeyCode::Tab) => {
-                self.edit_mode = match self.edit_mode {
-                    EditMode::Name => EditMode::Language,
-                    EditMode::Language => EditMode::Name,
-                };
+    /// Checks the form and fills in `project`, moving to the field that needs
+    /// fixing if there is one.
+    fn submit(&mut self) -> bool {
+        // A new directory needs a name that keeps it under the search path
+        if !self.roots.is_empty() {
+            if let Some(error) = dir_name_error(&self.project_name) {
+                self.error = Some(error);
+                self.edit_mode = EditMode::Name;
+                return false;
+            }
+        }
+        if !self.languages.contains(&self.selected_language) {
+            self.edit_mode = EditMode::Language;
+            return false;
+        }
+        let commands = match parse_entries(&self.commands) {
+            Ok(commands) => commands,
+            Err(error) => {
+                self.error = Some(error);
+                self.edit_mode = EditMode::Commands;
+                return false;
+            }
+        };
+        let env = match parse_entries(&self.env) {
+            Ok(env) => env,
+            Err(error) => {
+                self.error = Some(error);
+                self.edit_mode = EditMode::Env;
+                return false;
+            }
+        };
+
+        let project = &mut self.project;
+        project.name = Some(self.project_name.clone());
+        project.language = Some(self.selected_language.clone());
+        project.description = non_empty(&self.description);
+        project.tags = parse_tags(&self.tags.split(',').collect::<Vec<_>>());
+        project.editor = non_empty(&self.editor);
+        project.commands = commands;
+        project.env = env;
+        project.root = non_empty(&self.project_root);
+        project.hidden = self.hidden;
+        true
+    }
+
+    fn handle_input(&mut self, key: KeyEvent, keymap: &Keymap) -> bool {
+        let chord = KeyChord::from(key);
+        // Characters always go to the focused field, other keys to their action
+        let action = match keymap.lookup(&[chord], KeyContext::Form) {
+            Lookup::Action(action) => Some(action),
+            _ => None,
+        };
+        self.error = None;
+
+        match (self.edit_mode, key.code, action) {
+            (_, _, Some(Action::NextField)) => {
+                self.edit_mode = self.next_mode();
             }
-            (EditMode::Name, KeyCode::Char(c)) => {
-                self.project_name.push(c);
+            (EditMode::Location, KeyCode::Right, _) => {
+                self.root = (self.root + 1) % self.roots.len();
             }
-            (EditMode::Name, KeyCode::Backspace) => {
-                self.project_name.pop();
+            (EditMode::Location, KeyCode::Left, _) => {
+                self.root = (self.root + self.roots.len() - 1) % self.roots.len();
             }
-            (EditMode::Language, KeyCode::Char(c)) => {
-                self.selected_language.push(c);
+            (EditMode::Language, KeyCode::Char(c), _) if chord.is_text() => {
+                self.language_query.push(c);
                 self.filter_languages();
             }
-            (EditMode::Language, KeyCode::Backspace) => {
-                self.selected_language.pop();
+            (EditMode::Language, KeyCode::Backspace, _) => {
+                self.language_query.pop();
                 self.filter_languages();
             }
-            (EditMode::Language, KeyCode::Enter) => {
-                // Only allow Enter if the language exists in our filtered list
-                // or if it exactly matches "UNKNOWN"
-                if self.filtered_languages.len() == 1 {
-                    self.selected_language = self.filtered_languages[0].clone();
-                    return true;
-                } else if self.selected_language == "UNKNOWN" {
-                    return true;
+            (EditMode::Language, _, Some(Action::Down)) => self.move_highlight(1),
+            (EditMode::Language, _, Some(Action::Up)) => self.move_highlight(-1),
+            (EditMode::Language, _, Some(Action::Open)) => {
+                // Only allow Enter when a language from the list is highlighted
+                if let Some(lang) = self.highlighted_language() {
+                    self.selected_language = lang.clone();
+                    return self.submit();
                 }
                 return false;
             }
-            (EditMode::Name, KeyCode::Enter) => {
-                self.edit_mode = EditMode::Language;
+            (EditMode::Hidden, KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right, _) => {
+                self.hidden = !self.hidden;
+            }
+            (_, KeyCode::Char(c), _) if chord.is_text() => {
+                if let Some(text) = self.text_field() {
+                    text.push(c);
+                }
+            }
+            (_, KeyCode::Backspace, _) => {
+                if let Some(text) = self.text_field() {
+                    text.pop();
+                }
             }
-            (_, KeyCode::Esc) => std::process::exit(0),
+            (EditMode::Name | EditMode::Location, _, Some(Action::Open)) => {
+                self.edit_mode = self.next_mode();
+            }
+            (_, _, Some(Action::Open)) => return self.submit(),
+            (_, _, Some(Action::Quit)) => std::process::exit(0),
             _ => {}
         }
         false
     }
 }
 
-fn run_ui(mut terminal: DefaultTerminal) -> io::Result<(String, String)> {
-    let mut app = CreateApp::new();
+/// Asks for the project's name, language and details, and where to create it
+/// when `roots` isn't empty. The form starts out filled in from `project`.
+fn run_ui(
+    mut terminal: DefaultTerminal,
+    theme: &Theme,
+    keymap: &Keymap,
+    project: ProjectConfig,
+    roots: Vec<PathBuf>,
+    suggestions: Vec<Suggestion>,
+) -> io::Result<(ProjectConfig, Option<PathBuf>)> {
+    let mut app = CreateApp::new(project, roots, suggestions);
+    let location_height = if app.roots.is_empty() { 0 } else { 3 };
+    let details_height = EditMode::DETAILS.len() as u16 + 2;
 
     loop {
         terminal.draw(|frame| {
             let area = frame.area();
 
             let box_width = 60u16;
-            let box_height = 16u16; // Increased height for language suggestions
+            let box_height = 16u16 + location_height + details_height;
             let box_x = (area.width.saturating_sub(box_width)) / 2;
             let box_y = (area.height.saturating_sub(box_height)) / 2;
 
@@ -118,21 +452,23 @@ fn run_ui(mut terminal: DefaultTerminal) -> io::Result<(String, String)> {
                 .margin(1)
                 .constraints([
                     Constraint::Length(3), // Project name
+                    Constraint::Length(location_height),
                     Constraint::Length(3), // Language input
                     Constraint::Length(5), // Language suggestions
-                    Constraint::Min(1),    // Help text
+                    Constraint::Length(details_height),
+                    Constraint::Min(1), // Help text
                 ])
                 .split(centered_rect);
 
             // Project name input
             let name_style = if app.edit_mode == EditMode::Name {
-                Style::default().fg(Color::Yellow)
+                theme.selection
             } else {
-                Style::default()
+                theme.border
             };
 
             let name_block = Block::default()
-                .title("Project Name")
+                .title(EditMode::Name.label())
                 .borders(Borders::ALL)
                 .style(name_style);
 
@@ -141,92 +477,396 @@ fn run_ui(mut terminal: DefaultTerminal) -> io::Result<(String, String)> {
                 chunks[0],
             );
 
+            // Search root to create the project under
+            if let Some(root) = app.roots.get(app.root) {
+                let location_style = if app.edit_mode == EditMode::Location {
+                    theme.selection
+                } else {
+                    theme.border
+                };
+
+                let location_block = Block::default()
+                    .title(EditMode::Location.label())
+                    .borders(Borders::ALL)
+                    .style(location_style);
+
+                let location = root.join(&app.project_name);
+                frame.render_widget(
+                    Paragraph::new(format!("◂ {} ▸", location.display())).block(location_block),
+                    chunks[1],
+                );
+            }
+
             // Language input
             let lang_style = if app.edit_mode == EditMode::Language {
-                Style::default().fg(Color::Yellow)
+                theme.selection
             } else {
-                Style::default()
+                theme.border
             };
 
             let lang_block = Block::default()
-                .title("Language")
+                .title(EditMode::Language.label())
                 .borders(Borders::ALL)
                 .style(lang_style);
 
-            frame.render_widget(
-                Paragraph::new(app.selected_language.as_str()).block(lang_block),
-                chunks[1],
-            );
+            // Until something is typed the selection stands in as a placeholder
+            let lang_text = if app.language_query.is_empty() {
+                Span::styled(
+                    app.selected_language.as_str(),
+                    Style::default().add_modifier(Modifier::DIM),
+                )
+            } else {
+                Span::raw(app.language_query.as_str())
+            };
+            frame.render_widget(Paragraph::new(lang_text).block(lang_block), chunks[2]);
 
             // Language suggestions
             if app.edit_mode == EditMode::Language {
                 let suggestions: Vec<ListItem> = app
                     .filtered_languages
                     .iter()
-                    .map(|lang| ListItem::new(lang.as_str()))
+                    .map(|lang| {
+                        let confidence = app
+                            .suggestions
+                            .iter()
+                            .find(|s| &s.language == lang)
+                            .map(|s| format!(" ({:.0}%)", s.confidence * 100.0))
+                            .unwrap_or_default();
+                        ListItem::new(format!(
+                            "{:width$} {}{}",
+                            crate::languages::icon_for(lang),
+                            lang,
+                            confidence,
+                            width = crate::languages::icon_width()
+                        ))
+                    })
                     .collect();
 
-                let suggestions_list = List::new(suggestions).block(
-                    Block::default()
-                        .title("Available Languages")
-                        .borders(Borders::ALL),
-                );
+                let suggestions_list = List::new(suggestions)
+                    .block(
+                        Block::default()
+                            .title(Span::styled("Available Languages", theme.header))
+                            .borders(Borders::ALL)
+                            .border_style(theme.border),
+                    )
+                    .highlight_style(theme.selection)
+                    .highlight_symbol("> ");
 
-                frame.render_widget(suggestions_list, chunks[2]);
+                frame.render_stateful_widget(suggestions_list, chunks[3], &mut app.language_state);
             }
 
+            // Optional details, one field per line
+            let value_width = (chunks[4].width as usize).saturating_sub(LABEL_WIDTH + 2);
+            let details: Vec<Line> = EditMode::DETAILS
+                .iter()
+                .map(|&mode| {
+                    let value = app.detail(mode);
+                    let (style, value) = if app.edit_mode == mode {
+                        (theme.selection, tail(&value, value_width))
+                    } else {
+                        (Style::default(), value)
+                    };
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{:width$}", mode.label(), width = LABEL_WIDTH),
+                            theme.header,
+                        ),
+                        Span::raw(value),
+                    ])
+                    .style(style)
+                })
+                .collect();
+            frame.render_widget(
+                Paragraph::new(details).block(
+                    Block::default()
+                        .title(Span::styled("Details", theme.header))
+                        .borders(Borders::ALL)
+                        .border_style(theme.border),
+                ),
+                chunks[4],
+            );
+
             // Help text
+            let hint = |action| keymap.hint(action, KeyContext::Form);
+            let details_hint = |about: &str| {
+                format!(
+                    "{}{} for the next field | {} to save | {} to quit",
+                    about,
+                    hint(Action::NextField),
+                    hint(Action::Open),
+                    hint(Action::Quit)
+                )
+            };
             let help_text = match app.edit_mode {
-                EditMode::Name => "Tab/Enter to proceed to language | Esc to quit",
-                EditMode::Language => {
-                    if app.filtered_languages.len() == 1 {
-                        "Enter to select highlighted language | Esc to quit"
-                    } else {
-                        "Type to filter languages | Enter when one remains | Esc to quit"
-                    }
-                }
+                EditMode::Name if !app.roots.is_empty() => format!(
+                    "{}/{} to proceed to location | {} to quit",
+                    hint(Action::NextField),
+                    hint(Action::Open),
+                    hint(Action::Quit)
+                ),
+                EditMode::Name => format!(
+                    "{}/{} to proceed to language | {} to quit",
+                    hint(Action::NextField),
+                    hint(Action::Open),
+                    hint(Action::Quit)
+                ),
+                EditMode::Location => format!(
+                    "left/right to change | {} to proceed | {} to quit",
+                    hint(Action::Open),
+                    hint(Action::Quit)
+                ),
+                EditMode::Language => match app.highlighted_language() {
+                    Some(lang) => format!(
+                        "{}/{} to move | {} to pick {} | {} for details | {} to quit",
+                        hint(Action::Up),
+                        hint(Action::Down),
+                        hint(Action::Open),
+                        lang,
+                        hint(Action::NextField),
+                        hint(Action::Quit)
+                    ),
+                    None => format!(
+                        "No language matches, type to filter | {} to quit",
+                        hint(Action::Quit)
+                    ),
+                },
+                EditMode::Tags => details_hint("comma separated | "),
+                EditMode::Commands => details_hint("name=command; ... | "),
+                EditMode::Env => details_hint("NAME=value; ... | "),
+                EditMode::Root => details_hint("relative to the project | "),
+                EditMode::Hidden => details_hint("space to toggle | "),
+                EditMode::Description | EditMode::Editor => details_hint(""),
+            };
+            let help = match &app.error {
+                Some(error) => Paragraph::new(error.as_str()).style(theme.error),
+                None => Paragraph::new(help_text).style(theme.header),
             };
             frame.render_widget(
-                Paragraph::new(help_text).alignment(Alignment::Center),
-                chunks[3],
+                help.alignment(Alignment::Center).wrap(Wrap { trim: true }),
+                chunks[5],
             );
         })?;
 
         if let event::Event::Key(key) = event::read()? {
-            if app.handle_input(key.code) {
-                return Ok((app.project_name, app.selected_language));
+            if app.handle_input(key, keymap) {
+                let root = app.roots.get(app.root).cloned();
+                return Ok((app.project, root));
             }
         }
     }
 }
 
-pub fn create_project() -> Result<()> {
-    let project_file = Path::new(".dexproject");
+/// Language name as the form would pick it, failing on unknown languages.
+fn language_name(language: &str) -> Result<String> {
+    if language.eq_ignore_ascii_case("UNKNOWN") {
+        return Ok("UNKNOWN".to_string());
+    }
+    match Language::from_name(language) {
+        Some(lang) => Ok(lang.name().to_string()),
+        None => {
+            let known: Vec<&str> = crate::languages::all().iter().map(Language::name).collect();
+            bail!(
+                "Unknown language '{}', expected one of: {}, UNKNOWN",
+                language,
+                known.join(", ")
+            )
+        }
+    }
+}
+
+fn dir_name(dir: &Path) -> String {
+    fs::canonicalize(dir)
+        .ok()
+        .and_then(|dir| dir.file_name().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_default()
+}
+
+/// Fills in the details given on the command line.
+fn apply_args(project: &mut ProjectConfig, args: &MkArgs) -> Result<()> {
+    let tags = parse_tags(&args.tags);
+    if !tags.is_empty() {
+        project.tags = tags;
+    }
+    if let Some(description) = &args.description {
+        project.description = non_empty(description);
+    }
+    if let Some(editor) = &args.editor {
+        project.editor = non_empty(editor);
+    }
+    for entry in &args.commands {
+        let (name, command) = parse_entry(entry)
+            .with_context(|| format!("--command should be NAME=COMMAND, not '{}'", entry))?;
+        project.commands.insert(name, command);
+    }
+    for entry in &args.env {
+        let (name, value) = parse_entry(entry)
+            .with_context(|| format!("--env should be NAME=VALUE, not '{}'", entry))?;
+        project.env.insert(name, value);
+    }
+    if let Some(root) = &args.root {
+        project.root = non_empty(root);
+    }
+    if let Some(hidden) = args.hidden {
+        project.hidden = hidden;
+    }
+    Ok(())
+}
+
+/// Writes a `.dexproject` into the current directory, or `--dir`. With `--new`
+/// or `--template` the project goes in a new directory under one of the
+/// search paths instead, starting from the template or else the language's
+/// template.
+///
+/// The name, language and details are asked for unless `--language` is given.
+/// An existing `.dexproject` is edited rather than replaced, keeping the
+/// fields the form doesn't know about.
+pub fn create_project(config: &Config, keymap: &Keymap, args: &MkArgs) -> Result<()> {
+    // Fail on an unknown template before asking for anything
+    let template = args.template.as_deref().map(Template::named).transpose()?;
+    let scaffold = args.new || template.is_some();
+    let dir = args.dir.clone().unwrap_or_else(|| PathBuf::from("."));
 
-    if project_file.exists() {
-        // Check if user wants to overwrite
-        let mut overwrite = String::new();
-        println!("Project file already exists. Overwrite? (y/n)");
-        io::stdin().read_line(&mut overwrite)?;
-        if overwrite.trim() != "y" {
-            anyhow::bail!("Project file already exists");
+    // The search path to create a new directory under, unless `--dir` says
+    // where it goes
+    let roots = if scaffold && args.dir.is_none() {
+        if config.search_paths.is_empty() {
+            bail!("No search paths configured to create the project under");
         }
+        config.search_paths.clone()
+    } else {
+        Vec::new()
+    };
+
+    if !scaffold && !dir.is_dir() {
+        bail!("{} is not a directory", dir.display());
     }
+    let project_file = dir.join(".dexproject");
+    let existing = !scaffold && project_file.exists();
 
-    let mut terminal = ratatui::init();
-    terminal.clear()?;
+    if existing && !args.force {
+        if args.language.is_some() {
+            bail!(
+                "{} already exists, pass --force to rewrite it",
+                project_file.display()
+            );
+        }
+        let mut edit = String::new();
+        println!("Project file already exists. Edit it? (y/n)");
+        io::stdin().read_line(&mut edit)?;
+        if edit.trim() != "y" {
+            bail!("Project file already exists");
+        }
+    }
 
-    let (name, language) = run_ui(terminal)?;
+    // Rewriting a file that doesn't parse would throw away what's in it
+    let mut project = if existing {
+        ProjectConfig::read(&project_file).map_err(anyhow::Error::msg)?
+    } else {
+        ProjectConfig::default()
+    };
+    apply_args(&mut project, args)?;
+    if !scaffold && project.name.is_none() {
+        project.name = Some(dir_name(&dir));
+    }
 
-    ratatui::restore();
+    let (mut project, root) = match &args.language {
+        Some(language) => {
+            project.language = Some(language_name(language)?);
+            if let Some(name) = &args.name {
+                project.name = Some(name.clone());
+            } else if scaffold {
+                bail!("--name is needed to create a new directory");
+            }
+            (project, roots.first().cloned())
+        }
+        None => {
+            // A new directory is empty, there's nothing to suggest from
+            let suggestions = if scaffold {
+                Vec::new()
+            } else {
+                suggest_languages(&dir, &config.detection_rules())
+            };
+            let theme = Theme::new(&config.theme);
+            let mut terminal = ratatui::init();
+            terminal.clear()?;
 
-    let project_json = json!({
-        "name": name,
-        "language": language
-    });
+            let result = run_ui(terminal, &theme, keymap, project, roots, suggestions);
 
-    fs::write(project_file, serde_json::to_string_pretty(&project_json)?)
-        .context("Failed to write project file")?;
+            ratatui::restore();
+            result?
+        }
+    };
+
+    let name = project
+        .name
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or_default()
+        .to_string();
+    if name.is_empty() {
+        bail!("Project name can't be empty");
+    }
+    project.name = Some(name.clone());
+
+    if scaffold {
+        if let Some(error) = dir_name_error(&name) {
+            bail!(error);
+        }
+        let target = match root {
+            Some(root) => root.join(&name),
+            None => dir,
+        };
+        return scaffold_project(config, &target, project, template);
+    }
+
+    project.write(&project_file)
+}
+
+/// Creates `target` from the template and writes its `.dexproject`.
+fn scaffold_project(
+    config: &Config,
+    target: &Path,
+    project: ProjectConfig,
+    template: Option<Template>,
+) -> Result<()> {
+    if target.exists() {
+        bail!("{} already exists", target.display());
+    }
+    fs::create_dir_all(target).with_context(|| format!("Failed to create {}", target.display()))?;
+
+    let name = project.name.clone().unwrap_or_default();
+    let language = project.language.clone().unwrap_or_default();
+    if let Some(template) = template.or_else(|| Template::for_language(&language)) {
+        template.create(target, &Placeholders::new(&name, config.author.as_deref()))?;
+    }
+
+    // A template may come with its own `.dexproject`, e.g. for a layout, so
+    // only the fields that were filled in replace its own
+    let project_file = target.join(".dexproject");
+    let mut merged = if project_file.exists() {
+        ProjectConfig::read(&project_file).map_err(anyhow::Error::msg)?
+    } else {
+        ProjectConfig::default()
+    };
+    merged.name = project.name;
+    merged.language = project.language;
+    if project.description.is_some() {
+        merged.description = project.description;
+    }
+    if !project.tags.is_empty() {
+        merged.tags = project.tags;
+    }
+    if project.editor.is_some() {
+        merged.editor = project.editor;
+    }
+    merged.commands.extend(project.commands);
+    merged.env.extend(project.env);
+    if project.root.is_some() {
+        merged.root = project.root;
+    }
+    merged.hidden |= project.hidden;
+    merged.write(&project_file)?;
 
+    println!("Created {}", target.display());
     Ok(())
 }
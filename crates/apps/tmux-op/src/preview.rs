@@ -0,0 +1,188 @@
+use crate::git::{self, Commit, GitStatus};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+const MAX_FILES: usize = 30;
+const README_LINES: usize = 12;
+/// `.dexproject` fields the preview shows from the project itself.
+const PROJECT_FIELDS: [&str; 10] = [
+    "version",
+    "name",
+    "language",
+    "description",
+    "tags",
+    "editor",
+    "commands",
+    "env",
+    "root",
+    "hidden",
+];
+
+/// Details shown next to the project list for the highlighted project.
+#[derive(Debug)]
+pub struct Preview {
+    /// Top-level `.dexproject` fields as `key: value`, besides the ones the
+    /// browser already shows
+    pub metadata: Vec<(String, String)>,
+    pub git: Option<GitStatus>,
+    pub last_commit: Option<Commit>,
+    /// Top-level entries, directories first and suffixed with `/`
+    pub files: Vec<String>,
+    pub readme: Vec<String>,
+}
+
+fn read_metadata(directory: &Path) -> Vec<(String, String)> {
+    let Some(value) = fs::read_to_string(directory.join(".dexproject"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return Vec::new();
+    };
+
+    let Some(fields) = value.as_object() else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .filter(|(key, _)| !PROJECT_FIELDS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), describe_value(value)))
+        .collect()
+}
+
+/// Short one-line form of a `.dexproject` value, nested tables are only summarized.
+fn describe_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(items) if items.iter().all(|item| !item.is_object()) => items
+            .iter()
+            .map(describe_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+        serde_json::Value::Array(items) => format!("[{} entries]", items.len()),
+        serde_json::Value::Object(fields) => format!("{{{} fields}}", fields.len()),
+        other => other.to_string(),
+    }
+}
+
+fn list_files(directory: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(bool, String)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            (!is_dir, entry.file_name().to_string_lossy().to_string())
+        })
+        .collect();
+    files.sort();
+
+    files
+        .into_iter()
+        .take(MAX_FILES)
+        .map(|(is_file, name)| if is_file { name } else { name + "/" })
+        .collect()
+}
+
+fn read_readme(directory: &Path, files: &[String]) -> Vec<String> {
+    let Some(readme) = files
+        .iter()
+        .find(|name| name.to_lowercase().starts_with("readme"))
+    else {
+        return Vec::new();
+    };
+
+    fs::read_to_string(directory.join(readme))
+        .map(|content| {
+            content
+                .lines()
+                .take(README_LINES)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl Preview {
+    pub fn load(directory: &str) -> Self {
+        let path = Path::new(directory);
+        let files = list_files(path);
+        Preview {
+            metadata: read_metadata(path),
+            git: git::status(directory),
+            last_commit: git::last_commit(directory),
+            readme: read_readme(path, &files),
+            files,
+        }
+    }
+}
+
+/// Loads previews on a background thread so moving through the list never
+/// waits on git or the filesystem.
+pub struct PreviewLoader {
+    requests: Sender<String>,
+    results: Receiver<(String, Preview)>,
+    cache: HashMap<String, Preview>,
+    requested: Option<String>,
+}
+
+impl PreviewLoader {
+    pub fn new() -> Self {
+        let (requests, request_receiver) = mpsc::channel::<String>();
+        let (result_sender, results) = mpsc::channel();
+
+        thread::spawn(move || {
+            while let Ok(mut directory) = request_receiver.recv() {
+                // Only the latest request matters when scrolling quickly
+                while let Ok(newer) = request_receiver.try_recv() {
+                    directory = newer;
+                }
+
+                let preview = Preview::load(&directory);
+                if result_sender.send((directory, preview)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        PreviewLoader {
+            requests,
+            results,
+            cache: HashMap::new(),
+            requested: None,
+        }
+    }
+
+    /// Asks for the preview of `directory` to be loaded if it hasn't been yet.
+    pub fn request(&mut self, directory: &str) {
+        if !self.cache.contains_key(directory) && self.requested.as_deref() != Some(directory) {
+            let _ = self.requests.send(directory.to_string());
+            self.requested = Some(directory.to_string());
+        }
+    }
+
+    pub fn get(&self, directory: &str) -> Option<&Preview> {
+        self.cache.get(directory)
+    }
+
+    /// Stores previews that finished loading, returning whether there were any.
+    pub fn poll(&mut self) -> bool {
+        let mut received = false;
+        while let Ok((directory, preview)) = self.results.try_recv() {
+            self.cache.insert(directory, preview);
+            received = true;
+        }
+        received
+    }
+
+    /// Whether a preview is still being loaded.
+    pub fn is_loading(&self) -> bool {
+        self.requested
+            .as_ref()
+            .is_some_and(|directory| !self.cache.contains_key(directory))
+    }
+}
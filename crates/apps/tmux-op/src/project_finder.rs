@@ -1,88 +1,363 @@
-use anyhow::Result;
-use ignore::WalkBuilder;
+use crate::cache::ProjectIndex;
+use crate::config::DetectionRule;
+use crate::layout::{resolve_root, Layout};
+use anyhow::{bail, Context, Result};
+use ignore::{gitignore::Gitignore, WalkBuilder};
 use rayon::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
+    sync::mpsc::{self, Sender},
+    time::UNIX_EPOCH,
 };
 
-const IGNORED_DIRS: [&str; 5] = ["node_modules", "build", "target", "dist", "out"];
+pub const IGNORED_DIRS: [&str; 5] = ["node_modules", "build", "target", "dist", "out"];
 
-#[derive(Debug, Deserialize)]
+/// Version of the `.dexproject` schema this build writes. Files without a
+/// version predate it and only have a name, language, layout and tags.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Contents of a `.dexproject`.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ProjectConfig {
-    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Editor the built-in layout opens, also exported as `EDITOR`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// Named shell commands, like `build` or `test`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub commands: BTreeMap<String, String>,
+    /// Variables exported into every pane the project opens
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Directory the project opens in, relative to the `.dexproject`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    /// Left out of the browser unless hidden projects are shown
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+    /// Fields this build doesn't know about, kept so rewriting the file
+    /// doesn't lose them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug)]
+impl ProjectConfig {
+    /// Reads the `.dexproject` at `path`.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse JSON from {}: {}", path.display(), e))
+    }
+
+    /// Writes the file as the current schema version. A file from a newer
+    /// schema is left alone, since rewriting it could lose what it means.
+    pub fn write(&mut self, path: &Path) -> Result<()> {
+        if let Some(version) = self.version.filter(|&v| v > SCHEMA_VERSION) {
+            bail!(
+                "{} uses schema version {}, newer than the supported {}",
+                path.display(),
+                version,
+                SCHEMA_VERSION
+            );
+        }
+        self.version = Some(SCHEMA_VERSION);
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectInfo {
     pub name: String,
     pub language: String,
     pub directory: String,
+    /// Modification time of the `.dexproject` (seconds since the epoch), used
+    /// to decide whether a cached entry is still fresh.
+    #[serde(default)]
+    pub dexproject_mtime: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub commands: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Directory to open the project in, relative to `directory`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
 }
 
-pub fn find_project_files(root_dirs: &[PathBuf]) -> Result<Vec<ProjectInfo>> {
-    let projects: Vec<ProjectInfo> = root_dirs
+impl ProjectInfo {
+    /// Directory the project's panes start in.
+    pub fn open_directory(&self) -> String {
+        resolve_root(&self.directory, self.root.as_deref())
+    }
+
+    /// Variables to export into the project's panes, with the editor as
+    /// `EDITOR` unless `env` sets it.
+    pub fn pane_env(&self) -> BTreeMap<String, String> {
+        let mut env = self.env.clone();
+        if let Some(editor) = &self.editor {
+            env.entry("EDITOR".to_string())
+                .or_insert_with(|| editor.clone());
+        }
+        env
+    }
+}
+
+/// Progress reported while scanning for projects.
+#[derive(Debug)]
+pub enum ScanEvent {
+    /// Started walking a search root
+    Root(PathBuf),
+    Project(Box<ProjectInfo>),
+    Error(String),
+    /// Every search root has been walked
+    Done,
+}
+
+/// Walks `root_dirs` for projects and collects them. Parse errors are printed
+/// to stderr.
+pub fn find_project_files(
+    root_dirs: &[PathBuf],
+    rules: &[DetectionRule],
+    index: Option<&ProjectIndex>,
+) -> Result<Vec<ProjectInfo>> {
+    let (sender, receiver) = mpsc::channel();
+    let projects = scan_projects(root_dirs, rules, index, &sender);
+    drop(sender);
+
+    for event in receiver {
+        if let ScanEvent::Error(message) = event {
+            eprintln!("{}", message);
+        }
+    }
+
+    Ok(projects)
+}
+
+/// Walks `root_dirs` for projects, sending each one over `sender` as soon as
+/// it is found. Projects whose `.dexproject` is unchanged since they were added
+/// to `index` are reused instead of being parsed again.
+pub fn scan_projects(
+    root_dirs: &[PathBuf],
+    rules: &[DetectionRule],
+    index: Option<&ProjectIndex>,
+    sender: &Sender<ScanEvent>,
+) -> Vec<ProjectInfo> {
+    let projects = root_dirs
         .par_iter()
         .flat_map(|dir| {
-            eprintln!("Searching in: {}", dir.display());
-            let walker = WalkBuilder::new(dir)
-                .hidden(false)
-                .git_ignore(false)
-                .filter_entry(|entry| {
-                    !entry
-                        .path()
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .map_or(false, |name| IGNORED_DIRS.contains(&name))
-                })
-                .build();
-
-            // Collect paths first, then process them in parallel
-            let project_paths: Vec<_> = walker
-                .filter_map(Result::ok)
-                .filter(|entry| {
-                    entry
-                        .path()
-                        .file_name()
-                        .map(|name| name == ".dexproject")
-                        .unwrap_or(false)
-                })
-                .map(|entry| entry.into_path())
-                .collect();
-
-            // Process files in parallel
-            project_paths
-                .par_iter()
-                .filter_map(|path| {
-                    fs::read_to_string(path).ok().and_then(|content| {
-                        serde_json::from_str::<ProjectConfig>(&content)
-                            .map_err(|e| {
-                                eprintln!("Failed to parse JSON from {}: {}", path.display(), e);
-                            })
-                            .ok()
-                            .map(|config| {
-                                let project_dir = path.parent().unwrap_or_else(|| Path::new(""));
-                                let default_name = project_dir
-                                    .file_name()
-                                    .unwrap_or_default()
-                                    .to_string_lossy()
-                                    .to_string();
-                                ProjectInfo {
-                                    name: config.name.unwrap_or(default_name),
-                                    language: config
-                                        .language
-                                        .unwrap_or_else(|| "UNKNOWN".to_string())
-                                        .to_uppercase(),
-                                    directory: project_dir.to_string_lossy().to_string(),
-                                }
-                            })
-                    })
-                })
-                .collect::<Vec<_>>()
+            let _ = sender.send(ScanEvent::Root(dir.clone()));
+            scan_root(dir, rules, index, sender)
         })
         .collect();
 
-    Ok(projects)
+    let _ = sender.send(ScanEvent::Done);
+    projects
+}
+
+fn scan_root(
+    root: &Path,
+    rules: &[DetectionRule],
+    index: Option<&ProjectIndex>,
+    sender: &Sender<ScanEvent>,
+) -> Vec<ProjectInfo> {
+    // Only directories are walked; each one is checked for markers as it is
+    // entered, so its ancestors have always been checked before it
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .git_ignore(false)
+        .filter_entry(|entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            let ignored = entry
+                .path()
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| name == ".git" || IGNORED_DIRS.contains(&name));
+            is_dir && !ignored
+        })
+        .build();
+
+    let mut candidates: HashSet<PathBuf> = HashSet::new();
+    // Hidden and gitignored directories are still walked for `.dexproject`
+    // files, so their `.gitignore`s are kept to check what's below them
+    let mut gitignores: HashMap<PathBuf, Gitignore> = HashMap::new();
+    let mut projects = Vec::new();
+
+    for entry in walker.filter_map(Result::ok) {
+        let project_dir = entry.path();
+        let Ok(dir_entries) = fs::read_dir(project_dir) else {
+            continue;
+        };
+
+        let mut has_dexproject = false;
+        let mut has_gitignore = false;
+        // Index of the highest priority detection rule that matched
+        let mut rule: Option<usize> = None;
+        for file_name in dir_entries.filter_map(Result::ok).map(|e| e.file_name()) {
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            if file_name == ".dexproject" {
+                has_dexproject = true;
+            } else if file_name == ".gitignore" {
+                has_gitignore = true;
+            } else if let Some(idx) = rules.iter().position(|r| r.matches(file_name)) {
+                rule = Some(rule.map_or(idx, |r| r.min(idx)));
+            }
+        }
+
+        if has_gitignore {
+            let (gitignore, _) = Gitignore::new(project_dir.join(".gitignore"));
+            gitignores.insert(project_dir.to_path_buf(), gitignore);
+        }
+
+        if !has_dexproject && rule.is_none() {
+            continue;
+        }
+
+        // Marker-only directories nested inside another project are usually
+        // workspace members or vendored code, not projects of their own
+        let nested = project_dir
+            .ancestors()
+            .skip(1)
+            .any(|ancestor| candidates.contains(ancestor));
+
+        // Markers under dot-directories or gitignored paths are caches,
+        // registries and plugins rather than projects, so only a
+        // `.dexproject` counts there
+        let hidden = project_dir.strip_prefix(root).is_ok_and(|path| {
+            path.components()
+                .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+        });
+        let ignored = project_dir.ancestors().skip(1).any(|ancestor| {
+            gitignores.get(ancestor).is_some_and(|gitignore| {
+                gitignore
+                    .matched_path_or_any_parents(project_dir, true)
+                    .is_ignore()
+            })
+        });
+        let detected = if hidden || ignored {
+            None
+        } else {
+            rule.map(|idx| rules[idx].language.clone())
+        };
+        if !has_dexproject && detected.is_none() {
+            continue;
+        }
+
+        // A search root is a folder of projects rather than one, and a bare
+        // `.git` doesn't say enough to hide the language-detected projects
+        // below it
+        let weak = detected
+            .as_deref()
+            .is_some_and(|language| language.eq_ignore_ascii_case("UNKNOWN"))
+            && !has_dexproject;
+        if project_dir != root && !weak {
+            candidates.insert(project_dir.to_path_buf());
+        }
+        let project = if has_dexproject {
+            let mtime = dexproject_mtime(project_dir);
+            let cached =
+                index.and_then(|index| index.lookup(&project_dir.to_string_lossy(), mtime));
+            match cached {
+                Some(project) => Some(project.clone()),
+                None => read_dexproject(project_dir, detected, mtime)
+                    .map_err(|message| {
+                        let _ = sender.send(ScanEvent::Error(message));
+                    })
+                    .ok(),
+            }
+        } else if !nested && project_dir != root {
+            detected.map(|language| project_info(project_dir, None, language))
+        } else {
+            None
+        };
+
+        if let Some(project) = project {
+            let _ = sender.send(ScanEvent::Project(Box::new(project.clone())));
+            projects.push(project);
+        }
+    }
+
+    projects
+}
+
+fn dexproject_mtime(project_dir: &Path) -> Option<u64> {
+    fs::metadata(project_dir.join(".dexproject"))
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
+
+fn read_dexproject(
+    project_dir: &Path,
+    detected: Option<String>,
+    mtime: Option<u64>,
+) -> Result<ProjectInfo, String> {
+    let config = ProjectConfig::read(&project_dir.join(".dexproject"))?;
+
+    // An explicit language always wins over whatever the markers suggest
+    let language = config
+        .language
+        .or(detected)
+        .unwrap_or_else(|| "UNKNOWN".to_string());
+    let mut project = project_info(project_dir, config.name, language);
+    project.dexproject_mtime = mtime;
+    project.layout = config.layout;
+    project.tags = config.tags;
+    project.description = config.description;
+    project.editor = config.editor;
+    project.commands = config.commands;
+    project.env = config.env;
+    project.root = config.root;
+    project.hidden = config.hidden;
+    Ok(project)
+}
+
+fn project_info(project_dir: &Path, name: Option<String>, language: String) -> ProjectInfo {
+    let default_name = project_dir
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    ProjectInfo {
+        name: name.unwrap_or(default_name),
+        language: language.to_uppercase(),
+        directory: project_dir.to_string_lossy().to_string(),
+        dexproject_mtime: None,
+        layout: None,
+        tags: Vec::new(),
+        description: None,
+        editor: None,
+        commands: BTreeMap::new(),
+        env: BTreeMap::new(),
+        root: None,
+        hidden: false,
+    }
 }
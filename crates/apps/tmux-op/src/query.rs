@@ -0,0 +1,152 @@
+use crate::languages::Language;
+use crate::project_finder::ProjectInfo;
+
+/// What a query term is matched against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    /// The configured search fields, fuzzy matched
+    Text,
+    /// `lang:` or `language:`, a language name or alias
+    Language,
+    /// `tag:`, one of the project's tags
+    Tag,
+    /// `path:`, part of the project's directory
+    Path,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub field: Field,
+    pub text: String,
+    /// Written with a leading `!`, the term must not match
+    pub negated: bool,
+    /// Written in quotes, the term is matched literally instead of fuzzily
+    pub exact: bool,
+}
+
+/// A search typed into the browser, every term of which must match.
+///
+/// Terms are separated by spaces and can be:
+/// - `foo` fuzzy matched against the search fields
+/// - `"foo bar"` matched literally, quotes also allow spaces
+/// - `lang:rust`, `tag:work` or `path:clients/` matched against one field
+/// - any of the above prefixed with `!` to exclude projects that match it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+
+/// Splits `input` on whitespace outside of double quotes, noting whether each
+/// word contained quotes.
+fn split_words(input: &str) -> Vec<(String, bool)> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !word.is_empty() || quoted {
+                    words.push((std::mem::take(&mut word), quoted));
+                }
+                quoted = false;
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() || quoted {
+        words.push((word, quoted));
+    }
+    words
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let terms = split_words(input)
+            .into_iter()
+            .filter_map(|(word, exact)| {
+                let (negated, word) = match word.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, word.as_str()),
+                };
+
+                let (field, text) = match word.split_once(':') {
+                    Some(("lang" | "language", text)) => (Field::Language, text),
+                    Some(("tag", text)) => (Field::Tag, text),
+                    Some(("path", text)) => (Field::Path, text),
+                    _ => (Field::Text, word),
+                };
+
+                // A lone `!` or `tag:` while still typing filters nothing yet
+                (!text.is_empty()).then(|| Term {
+                    field,
+                    text: text.to_string(),
+                    negated,
+                    exact,
+                })
+            })
+            .collect();
+
+        Query { terms }
+    }
+
+    /// Terms that are fuzzy matched and scored.
+    pub fn fuzzy_terms(&self) -> impl Iterator<Item = &Term> {
+        self.terms
+            .iter()
+            .filter(|term| term.field == Field::Text && !term.negated && !term.exact)
+    }
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+impl Term {
+    /// Whether the project passes this term, ignoring fuzzy text terms which
+    /// are scored separately. `search_text` is the project's configured
+    /// search fields.
+    pub fn filter(&self, project: &ProjectInfo, search_text: &str) -> bool {
+        let matches = match self.field {
+            // Negated text is matched literally, fuzzily excluding would
+            // throw away far too much
+            Field::Text if self.exact || self.negated => {
+                contains_ignore_case(search_text, &self.text)
+            }
+            Field::Text => return true,
+            Field::Language => matches_language(&project.language, &self.text, self.exact),
+            Field::Tag => project.tags.iter().any(|tag| {
+                if self.exact {
+                    tag.eq_ignore_ascii_case(&self.text)
+                } else {
+                    contains_ignore_case(tag, &self.text)
+                }
+            }),
+            Field::Path => contains_ignore_case(&project.directory, &self.text),
+        };
+        matches != self.negated
+    }
+}
+
+/// Matches a language by any of its aliases, or by prefix so `lang:type`
+/// finds Typescript. Known languages only match themselves, so `lang:c`
+/// doesn't find C++.
+fn matches_language(language: &str, text: &str, exact: bool) -> bool {
+    if let Some(wanted) = Language::from_name(text) {
+        return wanted
+            .names
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(language));
+    }
+
+    if exact {
+        language.eq_ignore_ascii_case(text)
+    } else {
+        language.to_lowercase().starts_with(&text.to_lowercase())
+    }
+}
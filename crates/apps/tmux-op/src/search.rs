@@ -0,0 +1,208 @@
+use crate::config::{SearchField, SortKey};
+use crate::git::GitStatus;
+use crate::project_finder::ProjectInfo;
+use crate::query::{Field, Query};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+fn frecency_of(frecency: &HashMap<String, u64>, project: &ProjectInfo) -> u64 {
+    frecency.get(&project.directory).copied().unwrap_or(0)
+}
+
+/// A project matched by a search.
+#[derive(Debug, Clone)]
+pub struct Match {
+    /// Index into the searched projects
+    pub index: usize,
+    /// Matched character positions in the project's name
+    pub name_positions: Vec<usize>,
+    /// Matched character positions in the project's directory
+    pub directory_positions: Vec<usize>,
+}
+
+/// The configured search fields joined by spaces, remembering where the name
+/// and directory start so matches in them can be highlighted.
+struct SearchText {
+    text: String,
+    name_start: Option<usize>,
+    directory_start: Option<usize>,
+}
+
+impl SearchText {
+    fn new(project: &ProjectInfo, fields: &[SearchField]) -> Self {
+        let mut search = SearchText {
+            text: String::new(),
+            name_start: None,
+            directory_start: None,
+        };
+        for field in fields {
+            if !search.text.is_empty() {
+                search.text.push(' ');
+            }
+            let start = search.text.chars().count();
+            match field {
+                SearchField::Name => {
+                    search.name_start = Some(start);
+                    search.text.push_str(&project.name);
+                }
+                SearchField::Directory => {
+                    search.directory_start = Some(start);
+                    search.text.push_str(&project.directory);
+                }
+                SearchField::Language => search.text.push_str(&project.language),
+                SearchField::Tags => search.text.push_str(&project.tags.join(" ")),
+                SearchField::Description => search
+                    .text
+                    .push_str(project.description.as_deref().unwrap_or_default()),
+            }
+        }
+        search
+    }
+
+    /// Positions within `text` that fall in the field starting at `start`,
+    /// relative to that field.
+    fn positions_in(positions: &[usize], start: Option<usize>, len: usize) -> Vec<usize> {
+        let Some(start) = start else {
+            return Vec::new();
+        };
+        positions
+            .iter()
+            .filter(|&&i| i >= start && i < start + len)
+            .map(|&i| i - start)
+            .collect()
+    }
+}
+
+/// Character positions of the first case-insensitive occurrence of `needle`.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<Vec<usize>> {
+    let haystack: Vec<char> = haystack.chars().collect();
+    let needle: Vec<char> = needle.chars().collect();
+    let same = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+
+    (0..=haystack.len().checked_sub(needle.len())?)
+        .find(|&start| {
+            needle
+                .iter()
+                .enumerate()
+                .all(|(i, &c)| same(haystack[start + i], c))
+        })
+        .map(|start| (start..start + needle.len()).collect())
+}
+
+/// Matches `query` (see [`Query`]) against every project, returning the
+/// matches, best first. Fuzzy terms are matched against `fields`, with equal
+/// scores broken by frecency.
+pub fn rank_projects(
+    matcher: &SkimMatcherV2,
+    projects: &[ProjectInfo],
+    query: &str,
+    fields: &[SearchField],
+    frecency: &HashMap<String, u64>,
+) -> Vec<Match> {
+    let query = Query::parse(query);
+
+    let mut scored: Vec<(i64, u64, Match)> = projects
+        .iter()
+        .enumerate()
+        .filter_map(|(index, proj)| {
+            let search = SearchText::new(proj, fields);
+            if !query
+                .terms
+                .iter()
+                .all(|term| term.filter(proj, &search.text))
+            {
+                return None;
+            }
+
+            // Every fuzzy term has to match, their scores add up
+            let mut score = 0;
+            let mut positions = Vec::new();
+            for term in query.fuzzy_terms() {
+                let (term_score, term_positions) =
+                    matcher.fuzzy_indices(&search.text, &term.text)?;
+                score += term_score;
+                positions.extend(term_positions);
+            }
+
+            // Literal matches are highlighted too
+            let mut directory_positions = Vec::new();
+            for term in query.terms.iter().filter(|term| !term.negated) {
+                match term.field {
+                    Field::Text if term.exact => positions
+                        .extend(find_ignore_case(&search.text, &term.text).unwrap_or_default()),
+                    Field::Path => directory_positions
+                        .extend(find_ignore_case(&proj.directory, &term.text).unwrap_or_default()),
+                    _ => {}
+                }
+            }
+
+            let mut name_positions =
+                SearchText::positions_in(&positions, search.name_start, proj.name.chars().count());
+            directory_positions.extend(SearchText::positions_in(
+                &positions,
+                search.directory_start,
+                proj.directory.chars().count(),
+            ));
+            name_positions.sort_unstable();
+            name_positions.dedup();
+            directory_positions.sort_unstable();
+            directory_positions.dedup();
+
+            Some((
+                score,
+                frecency_of(frecency, proj),
+                Match {
+                    index,
+                    name_positions,
+                    directory_positions,
+                },
+            ))
+        })
+        .collect();
+
+    // Sort by score descending, then frecency descending
+    scored.sort_by_key(|&(score, frecency, _)| (Reverse(score), Reverse(frecency)));
+
+    scored.into_iter().map(|(_, _, m)| m).collect()
+}
+
+/// Orders every project by frecency, most used first, then by name.
+pub fn sort_by_frecency(projects: &[ProjectInfo], frecency: &HashMap<String, u64>) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..projects.len()).collect();
+    indices.sort_by_cached_key(|&idx| {
+        let project = &projects[idx];
+        (
+            Reverse(frecency_of(frecency, project)),
+            project.name.to_lowercase(),
+        )
+    });
+    indices
+}
+
+/// Orders every project by `key`, falling back to frecency and then name.
+/// Projects without git status sort last for the git based keys.
+pub fn sort_projects(
+    projects: &[ProjectInfo],
+    key: SortKey,
+    frecency: &HashMap<String, u64>,
+    git: &HashMap<String, GitStatus>,
+) -> Vec<usize> {
+    let mut indices = sort_by_frecency(projects, frecency);
+    // Stable sorts keep the frecency order among equal keys
+    match key {
+        SortKey::Frecency => {}
+        SortKey::Name => indices.sort_by_cached_key(|&idx| projects[idx].name.to_lowercase()),
+        SortKey::Committed => indices.sort_by_key(|&idx| {
+            Reverse(
+                git.get(&projects[idx].directory)
+                    .and_then(|status| status.last_commit_time),
+            )
+        }),
+        SortKey::Dirty => indices.sort_by_key(|&idx| {
+            Reverse(git.get(&projects[idx].directory).map(|status| status.dirty))
+        }),
+    }
+    indices
+}
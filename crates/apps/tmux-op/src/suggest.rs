@@ -0,0 +1,90 @@
+use crate::config::DetectionRule;
+use crate::languages::Language;
+use crate::project_finder::IGNORED_DIRS;
+use ignore::WalkBuilder;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Files counted before the mix of extensions is considered settled.
+const MAX_FILES: usize = 5000;
+/// How far below the directory files are counted.
+const MAX_DEPTH: usize = 4;
+
+/// A language a directory is probably written in.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub language: String,
+    /// Share of the evidence pointing at this language, from 0 to 1
+    pub confidence: f64,
+}
+
+/// Guesses the language of the project in `dir`, most likely first.
+///
+/// Each language whose marker is in `dir` scores 1, as much as all of the
+/// source files together, and the files below `dir` are shared out by
+/// extension. Languages with neither aren't suggested.
+pub fn suggest_languages(dir: &Path, rules: &[DetectionRule]) -> Vec<Suggestion> {
+    let mut scores: HashMap<&'static str, f64> = HashMap::new();
+
+    let file_names = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string));
+    for file_name in file_names {
+        // Markers like `.git` don't say anything about the language
+        let marked = rules
+            .iter()
+            .filter(|rule| rule.matches(&file_name))
+            .filter_map(|rule| Language::from_name(&rule.language));
+        for language in marked {
+            scores.insert(language.name(), 1.0);
+        }
+    }
+
+    let walker = WalkBuilder::new(dir)
+        .max_depth(Some(MAX_DEPTH))
+        .filter_entry(|entry| {
+            !entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| IGNORED_DIRS.contains(&name))
+        })
+        .build();
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    let files = walker
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .take(MAX_FILES);
+    for entry in files {
+        let language = entry
+            .path()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Language::from_extension);
+        if let Some(language) = language {
+            *counts.entry(language.name()).or_default() += 1;
+        }
+    }
+
+    let total: usize = counts.values().sum();
+    for (language, count) in counts {
+        *scores.entry(language).or_default() += count as f64 / total as f64;
+    }
+
+    let sum: f64 = scores.values().sum();
+    let mut suggestions: Vec<Suggestion> = scores
+        .into_iter()
+        .map(|(language, score)| Suggestion {
+            language: language.to_string(),
+            confidence: score / sum,
+        })
+        .collect();
+    suggestions.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then_with(|| a.language.cmp(&b.language))
+    });
+    suggestions
+}
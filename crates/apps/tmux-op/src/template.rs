@@ -0,0 +1,291 @@
+use crate::languages::Language;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Files a built-in template creates, as paths and contents.
+type Files = &'static [(&'static str, &'static str)];
+
+/// Minimal starting points for the built-in languages, keyed by their name.
+const BUILTIN: [(&str, Files); 7] = [
+    (
+        "C",
+        &[
+            ("README.md", "# {{name}}\n"),
+            (".gitignore", "/build\n"),
+            (
+                "Makefile",
+                "{{name}}: main.c\n\tmkdir -p build\n\t$(CC) -Wall -o build/{{name}} main.c\n",
+            ),
+            (
+                "main.c",
+                "#include <stdio.h>\n\nint main(void) {\n    printf(\"Hello from {{name}}\\n\");\n    return 0;\n}\n",
+            ),
+        ],
+    ),
+    (
+        "C++",
+        &[
+            ("README.md", "# {{name}}\n"),
+            (".gitignore", "/build\n"),
+            (
+                "CMakeLists.txt",
+                "cmake_minimum_required(VERSION 3.16)\nproject({{name}} CXX)\n\nset(CMAKE_CXX_STANDARD 20)\n\nadd_executable({{name}} main.cpp)\n",
+            ),
+            (
+                "main.cpp",
+                "#include <iostream>\n\nint main() {\n    std::cout << \"Hello from {{name}}\" << std::endl;\n}\n",
+            ),
+        ],
+    ),
+    (
+        "C#",
+        &[
+            ("README.md", "# {{name}}\n"),
+            (".gitignore", "bin/\nobj/\n"),
+            (
+                "{{name}}.csproj",
+                "<Project Sdk=\"Microsoft.NET.Sdk\">\n\n  <PropertyGroup>\n    <OutputType>Exe</OutputType>\n    <TargetFramework>net8.0</TargetFramework>\n    <Nullable>enable</Nullable>\n    <Authors>{{author}}</Authors>\n  </PropertyGroup>\n\n</Project>\n",
+            ),
+            ("Program.cs", "Console.WriteLine(\"Hello from {{name}}\");\n"),
+        ],
+    ),
+    (
+        "Typescript",
+        &[
+            ("README.md", "# {{name}}\n"),
+            (".gitignore", "node_modules/\ndist/\n"),
+            (
+                "package.json",
+                "{\n  \"name\": \"{{name}}\",\n  \"version\": \"0.1.0\",\n  \"author\": \"{{author}}\",\n  \"type\": \"module\",\n  \"scripts\": {\n    \"build\": \"tsc\"\n  },\n  \"devDependencies\": {\n    \"typescript\": \"^5.0.0\"\n  }\n}\n",
+            ),
+            (
+                "tsconfig.json",
+                "{\n  \"compilerOptions\": {\n    \"target\": \"ES2022\",\n    \"module\": \"NodeNext\",\n    \"strict\": true,\n    \"outDir\": \"dist\"\n  },\n  \"include\": [\"src\"]\n}\n",
+            ),
+            ("src/index.ts", "console.log(\"Hello from {{name}}\");\n"),
+        ],
+    ),
+    (
+        "Javascript",
+        &[
+            ("README.md", "# {{name}}\n"),
+            (".gitignore", "node_modules/\n"),
+            (
+                "package.json",
+                "{\n  \"name\": \"{{name}}\",\n  \"version\": \"0.1.0\",\n  \"author\": \"{{author}}\",\n  \"type\": \"module\",\n  \"main\": \"index.js\"\n}\n",
+            ),
+            ("index.js", "console.log(\"Hello from {{name}}\");\n"),
+        ],
+    ),
+    (
+        "Go",
+        &[
+            ("README.md", "# {{name}}\n"),
+            ("go.mod", "module {{name}}\n\ngo 1.22\n"),
+            (
+                "main.go",
+                "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(\"Hello from {{name}}\")\n}\n",
+            ),
+        ],
+    ),
+    (
+        "Rust",
+        &[
+            ("README.md", "# {{name}}\n"),
+            (".gitignore", "/target\n"),
+            (
+                "Cargo.toml",
+                "[package]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nedition = \"2021\"\nauthors = [\"{{author}}\"]\n\n[dependencies]\n",
+            ),
+            (
+                "src/main.rs",
+                "fn main() {\n    println!(\"Hello from {{name}}\");\n}\n",
+            ),
+        ],
+    ),
+];
+
+/// Values substituted for `{{name}}`, `{{author}}` and `{{date}}` in the
+/// paths and contents of a template's files.
+#[derive(Debug, Clone)]
+pub struct Placeholders {
+    pub name: String,
+    pub author: String,
+    /// Today as `YYYY-MM-DD`
+    pub date: String,
+}
+
+impl Placeholders {
+    /// Placeholders for a project called `name`. The author comes from
+    /// `author`, then git's `user.name`, then `$USER`.
+    pub fn new(name: &str, author: Option<&str>) -> Self {
+        let author = author
+            .map(str::to_string)
+            .or_else(git_user_name)
+            .or_else(|| std::env::var("USER").ok())
+            .unwrap_or_default();
+        Placeholders {
+            name: name.to_string(),
+            author,
+            date: today(),
+        }
+    }
+
+    fn apply(&self, text: &str) -> String {
+        text.replace("{{name}}", &self.name)
+            .replace("{{author}}", &self.author)
+            .replace("{{date}}", &self.date)
+    }
+}
+
+fn git_user_name() -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "user.name"])
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`.
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    // Days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Directory holding the user's templates, one directory each.
+pub fn templates_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("dex").join("templates"))
+}
+
+/// Names of the user's templates.
+fn user_templates() -> Vec<String> {
+    let Some(Ok(entries)) = templates_dir().map(fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Files a new project starts with.
+#[derive(Debug, Clone)]
+pub enum Template {
+    Builtin(Files),
+    /// A directory under [`templates_dir`] that is copied over
+    Directory(PathBuf),
+}
+
+impl Template {
+    /// The template called `name`: one of the user's, or the built-in one of
+    /// the language called that.
+    pub fn named(name: &str) -> Result<Self> {
+        if let Some(dir) = templates_dir().map(|dir| dir.join(name)) {
+            if dir.is_dir() {
+                return Ok(Template::Directory(dir));
+            }
+        }
+        if let Some(template) = Language::from_name(name).and_then(builtin) {
+            return Ok(template);
+        }
+
+        let mut available = user_templates();
+        available.extend(BUILTIN.iter().map(|(language, _)| language.to_string()));
+        bail!(
+            "No template named '{}', available: {}",
+            name,
+            available.join(", ")
+        )
+    }
+
+    /// The template new projects in `language` start from: a user template
+    /// named after the language or one of its aliases, else the built-in one.
+    pub fn for_language(language: &str) -> Option<Self> {
+        let names: Vec<&str> = match Language::from_name(language) {
+            Some(lang) => lang.names.iter().map(String::as_str).collect(),
+            None => vec![language],
+        };
+        let user = user_templates()
+            .into_iter()
+            .find(|template| names.iter().any(|n| n.eq_ignore_ascii_case(template)));
+        match (user, templates_dir()) {
+            (Some(template), Some(dir)) => Some(Template::Directory(dir.join(template))),
+            _ => Language::from_name(language).and_then(builtin),
+        }
+    }
+
+    /// Writes the template's files into `target`, which must already exist.
+    pub fn create(&self, target: &Path, placeholders: &Placeholders) -> Result<()> {
+        match self {
+            Template::Builtin(files) => {
+                for (path, content) in files.iter() {
+                    write_file(
+                        &target.join(placeholders.apply(path)),
+                        placeholders.apply(content).as_bytes(),
+                    )?;
+                }
+                Ok(())
+            }
+            Template::Directory(dir) => copy_dir(dir, target, placeholders),
+        }
+    }
+}
+
+fn builtin(language: &Language) -> Option<Template> {
+    BUILTIN
+        .iter()
+        .find(|(name, _)| language.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        .map(|(_, files)| Template::Builtin(files))
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Copies `from` into `to`, filling in placeholders in names and in the
+/// contents of text files.
+fn copy_dir(from: &Path, to: &Path, placeholders: &Placeholders) -> Result<()> {
+    let entries =
+        fs::read_dir(from).with_context(|| format!("Failed to read {}", from.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let name = placeholders.apply(&entry.file_name().to_string_lossy());
+        let target = to.join(name);
+
+        if entry.file_type()?.is_dir() {
+            fs::create_dir_all(&target)?;
+            copy_dir(&entry.path(), &target, placeholders)?;
+        } else {
+            let content = fs::read(entry.path())?;
+            match String::from_utf8(content) {
+                Ok(text) => write_file(&target, placeholders.apply(&text).as_bytes())?,
+                // Binary files are copied as they are
+                Err(err) => write_file(&target, err.as_bytes())?,
+            }
+        }
+    }
+    Ok(())
+}
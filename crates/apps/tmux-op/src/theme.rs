@@ -0,0 +1,138 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Whether colours are turned off, see https://no-color.org
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Parses a colour name like `yellow`, a 256 colour index like `214` or a
+/// hex code like `#fabd2f`.
+pub fn parse_color(color: &str) -> Option<Color> {
+    Color::from_str(color).ok()
+}
+
+/// Built-in set of colours a theme starts from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Default,
+    Gruvbox,
+    Nord,
+}
+
+/// The `[theme]` section of the config. Colours that are left out come from
+/// the preset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub preset: Preset,
+    /// Selected project and focused form field
+    pub selection: Option<String>,
+    pub border: Option<String>,
+    /// Help text and titles
+    pub header: Option<String>,
+    /// Search prompt while typing a query
+    pub search: Option<String>,
+    /// Project directories
+    pub path: Option<String>,
+    pub error: Option<String>,
+    /// Characters matched by the search
+    pub highlight: Option<String>,
+}
+
+/// Colours of a preset, `None` leaving the terminal's default.
+struct Palette {
+    selection: Option<&'static str>,
+    border: Option<&'static str>,
+    header: Option<&'static str>,
+    search: Option<&'static str>,
+    path: Option<&'static str>,
+    error: Option<&'static str>,
+    highlight: Option<&'static str>,
+}
+
+impl Preset {
+    fn palette(self) -> Palette {
+        match self {
+            Preset::Default => Palette {
+                selection: Some("yellow"),
+                border: None,
+                header: None,
+                search: Some("cyan"),
+                path: None,
+                error: Some("red"),
+                highlight: Some("cyan"),
+            },
+            Preset::Gruvbox => Palette {
+                selection: Some("#fabd2f"),
+                border: Some("#665c54"),
+                header: Some("#ebdbb2"),
+                search: Some("#8ec07c"),
+                path: Some("#a89984"),
+                error: Some("#fb4934"),
+                highlight: Some("#fe8019"),
+            },
+            Preset::Nord => Palette {
+                selection: Some("#88c0d0"),
+                border: Some("#4c566a"),
+                header: Some("#e5e9f0"),
+                search: Some("#a3be8c"),
+                path: Some("#7b88a1"),
+                error: Some("#bf616a"),
+                highlight: Some("#ebcb8b"),
+            },
+        }
+    }
+}
+
+/// Styles every part of the TUI is drawn with.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub selection: Style,
+    pub border: Style,
+    pub header: Style,
+    pub search: Style,
+    pub path: Style,
+    pub error: Style,
+    pub highlight: Style,
+}
+
+impl Theme {
+    /// Resolves `config` against its preset. With `NO_COLOR` set colours are
+    /// dropped and the selection is shown reversed instead.
+    pub fn new(config: &ThemeConfig) -> Self {
+        let palette = config.preset.palette();
+        let color = |name: &str, configured: &Option<String>, preset: Option<&str>| {
+            if no_color() {
+                return None;
+            }
+            match configured {
+                Some(value) => parse_color(value).or_else(|| {
+                    eprintln!("Ignoring unknown theme colour '{}' for {}", value, name);
+                    preset.and_then(parse_color)
+                }),
+                None => preset.and_then(parse_color),
+            }
+        };
+        let fg = |color: Option<Color>| color.map_or(Style::default(), |c| Style::default().fg(c));
+
+        let selection = color("selection", &config.selection, palette.selection);
+        let emphasis = Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+        Theme {
+            selection: match selection {
+                Some(c) => Style::default().fg(c),
+                None => Style::default().add_modifier(Modifier::REVERSED),
+            },
+            border: fg(color("border", &config.border, palette.border)),
+            header: fg(color("header", &config.header, palette.header)),
+            search: fg(color("search", &config.search, palette.search)),
+            path: fg(color("path", &config.path, palette.path)),
+            error: fg(color("error", &config.error, palette.error)),
+            highlight: emphasis.patch(fg(color("highlight", &config.highlight, palette.highlight))),
+        }
+    }
+}
@@ -1,17 +1,33 @@
-use crate::languages::Language;
-use crate::project_finder::ProjectInfo;
-use crossterm::event::KeyModifiers;
+use crate::config::{Config, GroupBy, OpenMode, SearchField, SortKey};
+use crate::git::{GitStatus, StatusLoader};
+use crate::keys::{Action, Context, KeyChord, Keymap, Lookup};
+use crate::languages;
+use crate::preview::PreviewLoader;
+use crate::project_finder::{ProjectInfo, ScanEvent};
+use crate::search::{rank_projects, sort_projects, Match};
+use crate::theme::Theme;
 use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
 use ratatui::prelude::*;
 use ratatui::{
-    crossterm::event::{self, KeyCode},
-    style::{Color, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    crossterm::{
+        event::{
+            self, DisableMouseCapture, EnableMouseCapture, KeyCode, MouseButton, MouseEvent,
+            MouseEventKind,
+        },
+        execute,
+    },
+    style::Style,
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Wrap,
+    },
     DefaultTerminal,
 };
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 fn truncate_str(s: &str, max_width: usize) -> String {
@@ -36,59 +52,564 @@ fn truncate_str(s: &str, max_width: usize) -> String {
     }
 }
 
+/// Which part of a string is shown once it's cut to fit, as a range of
+/// characters plus whether ellipses go before and after it.
+struct Window {
+    start: usize,
+    end: usize,
+    leading: bool,
+    trailing: bool,
+}
+
+/// Widest run of `chars` from `start` that fits in `budget` columns.
+fn fit_from(chars: &[(char, usize)], start: usize, budget: usize) -> usize {
+    let mut width = 0;
+    let mut end = start;
+    while end < chars.len() && width + chars[end].1 <= budget {
+        width += chars[end].1;
+        end += 1;
+    }
+    end
+}
+
+/// Picks the window of `chars` to show in `max_width` columns. Cutting the
+/// end is preferred, but the start or both ends are cut instead when that
+/// keeps more of the `highlights` in view.
+fn choose_window(chars: &[(char, usize)], highlights: &[usize], max_width: usize) -> Window {
+    let budget = max_width.saturating_sub(3);
+
+    let head = Window {
+        start: 0,
+        end: fit_from(chars, 0, budget),
+        leading: false,
+        trailing: true,
+    };
+
+    let mut tail_start = chars.len();
+    let mut width = 0;
+    while tail_start > 0 && width + chars[tail_start - 1].1 <= budget {
+        tail_start -= 1;
+        width += chars[tail_start].1;
+    }
+    let tail = Window {
+        start: tail_start,
+        end: chars.len(),
+        leading: true,
+        trailing: false,
+    };
+
+    // Start just before the first match, keeping a little context
+    let middle_start = highlights
+        .first()
+        .map_or(0, |&first| first.saturating_sub(2));
+    let middle = Window {
+        start: middle_start,
+        end: fit_from(chars, middle_start, max_width.saturating_sub(6)),
+        leading: true,
+        trailing: true,
+    };
+
+    let shown = |window: &Window| {
+        highlights
+            .iter()
+            .filter(|&&i| i >= window.start && i < window.end)
+            .count()
+    };
+    // `max_by_key` keeps the last of equals, so go from least to most preferred
+    [middle, tail, head]
+        .into_iter()
+        .max_by_key(|window| shown(window))
+        .unwrap()
+}
+
+/// Like `truncate_str`, but styles the characters at `highlights` (character
+/// positions in `s`) and avoids cutting them off where it can.
+fn truncate_highlighted(
+    s: &str,
+    highlights: &[usize],
+    max_width: usize,
+    style: Style,
+    highlight_style: Style,
+) -> Vec<Span<'static>> {
+    let chars: Vec<(char, usize)> = s
+        .chars()
+        .map(|c| (c, UnicodeWidthChar::width(c).unwrap_or(1)))
+        .collect();
+
+    let window = if s.width() <= max_width {
+        Window {
+            start: 0,
+            end: chars.len(),
+            leading: false,
+            trailing: false,
+        }
+    } else {
+        choose_window(&chars, highlights, max_width)
+    };
+
+    let mut spans = Vec::new();
+    let mut width = 0;
+    if window.leading {
+        spans.push(Span::styled("...", style));
+        width += 3;
+    }
+
+    // Group runs of equally styled characters into one span
+    let mut run = String::new();
+    let mut run_highlighted = false;
+    for (i, &(c, char_width)) in chars[window.start..window.end].iter().enumerate() {
+        let highlighted = highlights.contains(&(window.start + i));
+        if highlighted != run_highlighted && !run.is_empty() {
+            let run_style = if run_highlighted {
+                style.patch(highlight_style)
+            } else {
+                style
+            };
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+        run.push(c);
+        run_highlighted = highlighted;
+        width += char_width;
+    }
+    if !run.is_empty() {
+        let run_style = if run_highlighted {
+            style.patch(highlight_style)
+        } else {
+            style
+        };
+        spans.push(Span::styled(run, run_style));
+    }
+
+    if window.trailing {
+        spans.push(Span::styled("...", style));
+        width += 3;
+    }
+    spans.push(Span::raw(" ".repeat(max_width.saturating_sub(width))));
+    spans
+}
+
 fn prettify_home(s: &str) -> String {
     let home = dirs::home_dir().unwrap();
     let home_str = home.to_str().unwrap();
     s.replace(home_str, "~")
 }
 
+/// Shortens a home directory prefix to `~` like `prettify_home`, moving the
+/// character `positions` along with it.
+fn prettify_home_positions(s: &str, positions: &[usize]) -> (String, Vec<usize>) {
+    let home = dirs::home_dir().unwrap();
+    let Some(rest) = s.strip_prefix(home.to_str().unwrap()) else {
+        return (s.to_string(), positions.to_vec());
+    };
+
+    let home_len = s.chars().count() - rest.chars().count();
+    let mut moved: Vec<usize> = positions
+        .iter()
+        .map(|&i| if i < home_len { 0 } else { i - home_len + 1 })
+        .collect();
+    moved.dedup();
+    (format!("~{}", rest), moved)
+}
+
+/// Compact age of a commit made at `timestamp`, like `3d` or `5mo`.
+fn format_age(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let age = now.saturating_sub(timestamp);
+    match age {
+        age if age < 60 * 60 => format!("{}m", age / 60),
+        age if age < 24 * 60 * 60 => format!("{}h", age / (60 * 60)),
+        age if age < 7 * 24 * 60 * 60 => format!("{}d", age / (24 * 60 * 60)),
+        age if age < 30 * 24 * 60 * 60 => format!("{}w", age / (7 * 24 * 60 * 60)),
+        age if age < 365 * 24 * 60 * 60 => format!("{}mo", age / (30 * 24 * 60 * 60)),
+        age => format!("{}y", age / (365 * 24 * 60 * 60)),
+    }
+}
+
+/// Dirty marker plus ahead/behind counts, like `● ↑2↓1`.
+fn format_git_state(status: &GitStatus) -> String {
+    let mut state = String::from(if status.dirty { "●" } else { "✓" });
+    if let Some((ahead, behind)) = status.ahead_behind {
+        if ahead > 0 || behind > 0 {
+            state.push(' ');
+        }
+        if ahead > 0 {
+            state.push_str(&format!("↑{}", ahead));
+        }
+        if behind > 0 {
+            state.push_str(&format!("↓{}", behind));
+        }
+    }
+    state
+}
+
+const BRANCH_WIDTH: usize = 14;
+const STATE_WIDTH: usize = 7;
+const AGE_WIDTH: usize = 4;
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Rows moved by one step of the scroll wheel
+const SCROLL_ROWS: isize = 3;
+/// Longest gap between the clicks of a double click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// State of a scan that is streaming projects into the browser.
+struct ScanProgress {
+    receiver: Receiver<ScanEvent>,
+    started: Instant,
+    current_root: Option<PathBuf>,
+    found: usize,
+    errors: usize,
+    // Directories reported by this scan, anything else is stale once it finishes
+    seen: HashSet<String>,
+}
+
+impl ScanProgress {
+    fn status(&self, theme: &Theme) -> Line<'static> {
+        let frame = (self.started.elapsed().as_millis() / 80) as usize % SPINNER.len();
+        let root = self
+            .current_root
+            .as_ref()
+            .map(|root| prettify_home(&root.to_string_lossy()))
+            .unwrap_or_default();
+        let mut status = vec![Span::raw(format!(
+            " {} Scanning {} ({} found) ",
+            SPINNER[frame], root, self.found
+        ))];
+        if self.errors > 0 {
+            status.push(Span::styled(
+                format!("({} errors) ", self.errors),
+                theme.error,
+            ));
+        }
+        Line::from(status)
+    }
+}
+
+fn icon_style(language: &str) -> Style {
+    languages::color_for(language).map_or(Style::default(), |color| Style::default().fg(color))
+}
+
+/// A line in the project list.
+#[derive(Debug, Clone, PartialEq)]
+enum Row {
+    /// Header of a group, followed by its projects unless collapsed
+    Group {
+        key: String,
+        count: usize,
+    },
+    Project(usize),
+}
+
+/// Popup for picking the language to filter by.
+struct LanguageChooser {
+    /// Each language with its number of projects, `None` showing every language
+    options: Vec<(Option<String>, usize)>,
+    state: ListState,
+}
+
 pub struct App {
     projects: Vec<ProjectInfo>,
     selected: usize,
+    /// Set when the selection is a group header rather than `selected`
+    selected_group: Option<String>,
     search_active: bool,
     search_query: String,
     filtered_indices: Vec<usize>,
+    /// Matched character positions in each result's name and directory
+    highlights: HashMap<usize, Match>,
+    search_fields: Vec<SearchField>,
     matcher: SkimMatcherV2,
+    frecency: HashMap<String, u64>,
+    scan: Option<ScanProgress>,
+    show_preview: bool,
+    previews: PreviewLoader,
+    sort: SortKey,
+    git: HashMap<String, GitStatus>,
+    statuses: StatusLoader,
+    list_state: ListState,
+    /// Rows visible in the list, as of the last draw
+    page_height: usize,
+    /// Where the list was last drawn, to find the row under a click
+    list_area: Rect,
+    /// Row and time of the last click, to spot double clicks
+    last_click: Option<(usize, Instant)>,
+    /// Keys of a binding typed so far, like the first `g` of `g g`
+    pending_keys: Vec<KeyChord>,
+    /// What's shown in the list, `filtered_indices` split into groups
+    rows: Vec<Row>,
+    group_by: GroupBy,
+    collapsed: HashSet<String>,
+    language_filter: Option<String>,
+    language_chooser: Option<LanguageChooser>,
+    /// Whether projects marked `hidden` are listed
+    show_hidden: bool,
+    search_roots: Vec<PathBuf>,
+    theme: Theme,
+    keymap: Keymap,
+    show_help: bool,
+    open_mode: OpenMode,
 }
 
 impl App {
-    pub fn new(projects: Vec<ProjectInfo>) -> Self {
-        let indices: Vec<usize> = (0..projects.len()).collect();
+    pub fn new(
+        projects: Vec<ProjectInfo>,
+        scan: Receiver<ScanEvent>,
+        frecency: HashMap<String, u64>,
+    ) -> Self {
+        let git = HashMap::new();
+        let indices = sort_projects(&projects, SortKey::default(), &frecency, &git);
+        let mut statuses = StatusLoader::new();
+        for project in &projects {
+            statuses.request(&project.directory);
+        }
         Self {
+            selected: indices.first().copied().unwrap_or(0),
+            selected_group: None,
+            rows: indices.iter().map(|&idx| Row::Project(idx)).collect(),
             projects,
-            selected: 0,
             search_active: false,
             search_query: String::new(),
             filtered_indices: indices,
+            highlights: HashMap::new(),
+            search_fields: Vec::new(),
             matcher: SkimMatcherV2::default(),
+            frecency,
+            show_preview: false,
+            previews: PreviewLoader::new(),
+            sort: SortKey::default(),
+            git,
+            statuses,
+            list_state: ListState::default(),
+            page_height: 0,
+            list_area: Rect::default(),
+            last_click: None,
+            pending_keys: Vec::new(),
+            group_by: GroupBy::default(),
+            collapsed: HashSet::new(),
+            language_filter: None,
+            language_chooser: None,
+            show_hidden: false,
+            search_roots: Vec::new(),
+            theme: Theme::new(&Default::default()),
+            keymap: Keymap::default(),
+            show_help: false,
+            open_mode: OpenMode::default(),
+            scan: Some(ScanProgress {
+                receiver: scan,
+                started: Instant::now(),
+                current_root: None,
+                found: 0,
+                errors: 0,
+                seen: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Applies every scan event that has arrived since the last call.
+    fn poll_scan(&mut self) {
+        let Some(scan) = &mut self.scan else {
+            return;
+        };
+
+        let mut changed = false;
+        let mut finished = false;
+        loop {
+            match scan.receiver.try_recv() {
+                Ok(ScanEvent::Root(root)) => scan.current_root = Some(root),
+                Ok(ScanEvent::Project(project)) => {
+                    scan.found += 1;
+                    scan.seen.insert(project.directory.clone());
+                    match self
+                        .projects
+                        .iter_mut()
+                        .find(|p| p.directory == project.directory)
+                    {
+                        Some(existing) => *existing = *project,
+                        None => {
+                            self.statuses.request(&project.directory);
+                            self.projects.push(*project);
+                        }
+                    }
+                    changed = true;
+                }
+                Ok(ScanEvent::Error(_)) => scan.errors += 1,
+                Ok(ScanEvent::Done) | Err(TryRecvError::Disconnected) => {
+                    finished = true;
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        if finished {
+            if let Some(scan) = self.scan.take() {
+                self.remove_stale(&scan.seen);
+            }
+            changed = true;
+        }
+
+        if changed {
+            self.refresh();
+        }
+    }
+
+    /// Stores git statuses that finished loading, re-sorting if the order
+    /// depends on them.
+    fn poll_git(&mut self) {
+        let results = self.statuses.poll();
+        if results.is_empty() {
+            return;
+        }
+
+        for (directory, status) in results {
+            match status {
+                Some(status) => self.git.insert(directory, status),
+                None => self.git.remove(&directory),
+            };
+        }
+        if self.sort.uses_git() {
+            self.refresh();
+        }
+    }
+
+    fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.refresh();
+    }
+
+    /// Drops cached projects that the finished scan didn't find again.
+    fn remove_stale(&mut self, seen: &HashSet<String>) {
+        let selected_dir = self
+            .projects
+            .get(self.selected)
+            .map(|project| project.directory.clone());
+
+        self.projects.retain(|p| seen.contains(&p.directory));
+
+        self.selected = selected_dir
+            .and_then(|dir| self.projects.iter().position(|p| p.directory == dir))
+            .unwrap_or(0);
+    }
+
+    fn is_selected(&self, row: &Row) -> bool {
+        match row {
+            Row::Group { key, .. } => self.selected_group.as_ref() == Some(key),
+            Row::Project(idx) => self.selected_group.is_none() && *idx == self.selected,
+        }
+    }
+
+    /// Position of the selection within the list.
+    fn selected_position(&self) -> Option<usize> {
+        self.rows.iter().position(|row| self.is_selected(row))
+    }
+
+    fn select_row(&mut self, pos: usize) {
+        match &self.rows[pos] {
+            Row::Group { key, .. } => self.selected_group = Some(key.clone()),
+            Row::Project(idx) => {
+                self.selected = *idx;
+                self.selected_group = None;
+            }
+        }
+    }
+
+    /// Moves the selection by `delta` rows, stopping at either end.
+    fn move_by(&mut self, delta: isize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let current_pos = self.selected_position().unwrap_or(0);
+        let last = self.rows.len() - 1;
+        self.select_row(current_pos.saturating_add_signed(delta).min(last));
+    }
+
+    fn page_down(&mut self) {
+        self.move_by(self.page_height.max(1) as isize);
+    }
+
+    fn page_up(&mut self) {
+        self.move_by(-(self.page_height.max(1) as isize));
+    }
+
+    fn half_page_down(&mut self) {
+        self.move_by((self.page_height / 2).max(1) as isize);
+    }
+
+    fn half_page_up(&mut self) {
+        self.move_by(-((self.page_height / 2).max(1) as isize));
+    }
+
+    /// Position of the row drawn at a terminal cell, if there is one.
+    fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let inner = self.list_area.inner(Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+        if !inner.contains(Position::new(column, row)) {
+            return None;
+        }
+        let pos = self.list_state.offset() + (row - inner.y) as usize;
+        (pos < self.rows.len()).then_some(pos)
+    }
+
+    /// Selects the clicked row or scrolls the list. Returns whether the click
+    /// was the second of a double click on the same row.
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> bool {
+        match mouse.kind {
+            MouseEventKind::ScrollDown => self.move_by(SCROLL_ROWS),
+            MouseEventKind::ScrollUp => self.move_by(-SCROLL_ROWS),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some(pos) = self.row_at(mouse.column, mouse.row) else {
+                    self.last_click = None;
+                    return false;
+                };
+                self.select_row(pos);
+
+                let now = Instant::now();
+                let double = self
+                    .last_click
+                    .is_some_and(|(last, at)| last == pos && now.duration_since(at) < DOUBLE_CLICK);
+                // A third click starts over rather than opening again
+                self.last_click = (!double).then_some((pos, now));
+                return double;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn select_first(&mut self) {
+        if !self.rows.is_empty() {
+            self.select_row(0);
+        }
+    }
+
+    fn select_last(&mut self) {
+        if !self.rows.is_empty() {
+            self.select_row(self.rows.len() - 1);
         }
     }
 
     pub fn next(&mut self) {
-        if !self.filtered_indices.is_empty() {
-            let current_pos = self
-                .filtered_indices
-                .iter()
-                .position(|&x| x == self.selected)
-                .unwrap_or(0);
-            let next_pos = (current_pos + 1) % self.filtered_indices.len();
-            self.selected = self.filtered_indices[next_pos];
+        if !self.rows.is_empty() {
+            let current_pos = self.selected_position().unwrap_or(0);
+            self.select_row((current_pos + 1) % self.rows.len());
         }
     }
 
     pub fn previous(&mut self) {
-        if !self.filtered_indices.is_empty() {
-            let current_pos = self
-                .filtered_indices
-                .iter()
-                .position(|&x| x == self.selected)
-                .unwrap_or(0);
+        if !self.rows.is_empty() {
+            let current_pos = self.selected_position().unwrap_or(0);
             let prev_pos = if current_pos > 0 {
                 current_pos - 1
             } else {
-                self.filtered_indices.len() - 1
+                self.rows.len() - 1
             };
-            self.selected = self.filtered_indices[prev_pos];
+            self.select_row(prev_pos);
         }
     }
 
@@ -102,77 +623,691 @@ impl App {
         self.filter_projects();
     }
 
+    /// Re-applies the search after the project list changed, keeping the
+    /// current selection where possible.
+    fn refresh(&mut self) {
+        self.apply_filter();
+        if self.selected_position().is_some() {
+            return;
+        }
+
+        // A project hidden in a collapsed group leaves the selection on its header
+        let hidden_in = self
+            .filtered_indices
+            .contains(&self.selected)
+            .then(|| self.group_key(&self.projects[self.selected]))
+            .flatten();
+        match hidden_in {
+            Some(key) if self.collapsed.contains(&key) => self.selected_group = Some(key),
+            _ => self.select_first(),
+        }
+    }
+
     fn filter_projects(&mut self) {
+        self.apply_filter();
+
+        // Update selected to first match if we have results
+        if !self.search_query.is_empty() {
+            if let Some(pos) = self
+                .rows
+                .iter()
+                .position(|row| matches!(row, Row::Project(_)))
+            {
+                self.select_row(pos);
+            }
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        self.highlights.clear();
         if self.search_query.is_empty() {
-            self.filtered_indices = (0..self.projects.len()).collect();
+            self.filtered_indices =
+                sort_projects(&self.projects, self.sort, &self.frecency, &self.git);
+        } else {
+            let matches = rank_projects(
+                &self.matcher,
+                &self.projects,
+                &self.search_query,
+                &self.search_fields,
+                &self.frecency,
+            );
+            self.filtered_indices = matches.iter().map(|m| m.index).collect();
+            self.highlights = matches.into_iter().map(|m| (m.index, m)).collect();
+        }
+
+        if let Some(language) = &self.language_filter {
+            self.filtered_indices
+                .retain(|&idx| self.projects[idx].language == *language);
+        }
+        if !self.show_hidden {
+            self.filtered_indices
+                .retain(|&idx| !self.projects[idx].hidden);
+        }
+        self.build_rows();
+    }
+
+    /// Group a project is listed under, `None` when not grouping.
+    fn group_key(&self, project: &ProjectInfo) -> Option<String> {
+        match self.group_by {
+            GroupBy::None => None,
+            GroupBy::Language => Some(project.language.clone()),
+            GroupBy::Root => {
+                // Search roots can be nested, the closest one wins
+                let root = self
+                    .search_roots
+                    .iter()
+                    .filter(|root| Path::new(&project.directory).starts_with(root))
+                    .max_by_key(|root| root.as_os_str().len());
+                Some(match root {
+                    Some(root) => prettify_home(&root.to_string_lossy()),
+                    None => "Other".to_string(),
+                })
+            }
+        }
+    }
+
+    /// Splits the filtered projects into groups, ordered by their best
+    /// ranked project so sorting and searching still apply.
+    fn build_rows(&mut self) {
+        if self.group_by == GroupBy::None {
+            self.rows = self
+                .filtered_indices
+                .iter()
+                .map(|&idx| Row::Project(idx))
+                .collect();
+            return;
+        }
+
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        for &idx in &self.filtered_indices {
+            let key = self.group_key(&self.projects[idx]).unwrap_or_default();
+            match groups.iter_mut().find(|(group, _)| *group == key) {
+                Some((_, members)) => members.push(idx),
+                None => groups.push((key, vec![idx])),
+            }
+        }
+
+        self.rows = Vec::new();
+        for (key, members) in groups {
+            let collapsed = self.collapsed.contains(&key);
+            self.rows.push(Row::Group {
+                key,
+                count: members.len(),
+            });
+            if !collapsed {
+                self.rows.extend(members.into_iter().map(Row::Project));
+            }
+        }
+    }
+
+    fn cycle_grouping(&mut self) {
+        self.group_by = self.group_by.next();
+        self.selected_group = None;
+        self.refresh();
+    }
+
+    /// Group the selection belongs to, or is the header of.
+    fn selected_group_key(&self) -> Option<String> {
+        match &self.selected_group {
+            Some(key) => Some(key.clone()),
+            None => self
+                .selected_project()
+                .and_then(|project| self.group_key(project)),
+        }
+    }
+
+    /// Collapses or expands the selected group, leaving the selection on its
+    /// header.
+    fn set_collapsed(&mut self, collapsed: bool) {
+        let Some(key) = self.selected_group_key() else {
             return;
+        };
+        if collapsed {
+            self.collapsed.insert(key.clone());
+        } else {
+            self.collapsed.remove(&key);
         }
+        self.selected_group = Some(key);
+        self.build_rows();
+    }
+
+    fn toggle_collapsed(&mut self) {
+        let collapsed = self
+            .selected_group_key()
+            .is_some_and(|key| self.collapsed.contains(&key));
+        self.set_collapsed(!collapsed);
+    }
 
-        let mut scored_indices: Vec<(i64, usize)> = self
+    fn open_language_chooser(&mut self) {
+        let projects: Vec<&ProjectInfo> = self
             .projects
             .iter()
-            .enumerate()
-            .filter_map(|(idx, proj)| {
-                let search_text = format!("{} {}", proj.name, proj.directory);
-                self.matcher
-                    .fuzzy_match(&search_text, &self.search_query)
-                    .map(|score| (score, idx))
-            })
+            .filter(|project| self.show_hidden || !project.hidden)
             .collect();
+        let mut counts: Vec<(Option<String>, usize)> = Vec::new();
+        for project in &projects {
+            match counts
+                .iter_mut()
+                .find(|(language, _)| language.as_ref() == Some(&project.language))
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((Some(project.language.clone()), 1)),
+            }
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
-        // Sort by score descending
-        scored_indices.sort_by(|a, b| b.0.cmp(&a.0));
+        let mut options = vec![(None, projects.len())];
+        options.extend(counts);
 
-        self.filtered_indices = scored_indices.into_iter().map(|(_, idx)| idx).collect();
+        let current = options
+            .iter()
+            .position(|(language, _)| *language == self.language_filter);
+        self.language_chooser = Some(LanguageChooser {
+            options,
+            state: ListState::default().with_selected(current.or(Some(0))),
+        });
+    }
 
-        // Update selected to first match if we have results
-        if let Some(&first_match) = self.filtered_indices.first() {
-            self.selected = first_match;
+    fn set_language_filter(&mut self, language: Option<String>) {
+        self.language_filter = language;
+        self.selected_group = None;
+        self.refresh();
+    }
+
+    fn selected_project(&self) -> Option<&ProjectInfo> {
+        (self.selected_group.is_none() && self.filtered_indices.contains(&self.selected))
+            .then(|| &self.projects[self.selected])
+    }
+
+    /// Adds `key` to the keys typed so far and returns the action they are
+    /// bound to, if they are a whole binding.
+    fn resolve_key(&mut self, key: KeyChord, context: Context) -> Option<Action> {
+        self.pending_keys.push(key);
+        loop {
+            match self.keymap.lookup(&self.pending_keys, context) {
+                Lookup::Action(action) => {
+                    self.pending_keys.clear();
+                    return Some(action);
+                }
+                Lookup::Pending => return None,
+                // Abandon a half typed sequence, the last key may start another
+                Lookup::None if self.pending_keys.len() > 1 => self.pending_keys = vec![key],
+                Lookup::None => {
+                    self.pending_keys.clear();
+                    return None;
+                }
+            }
         }
     }
 
-    pub fn open_in_tmux(&self) -> io::Result<()> {
-        if let Some(project) = self.projects.get(self.selected) {
-            // Create new tmux window in project directory
-            Command::new("tmux")
-                .args(["new-window", "-c", &project.directory])
-                .status()?;
+    /// Takes the selected project out of the app once the browser is done.
+    fn into_selected(mut self, mode: OpenMode) -> Option<(ProjectInfo, OpenMode)> {
+        if self.selected_group.is_none() && self.filtered_indices.contains(&self.selected) {
+            Some((self.projects.swap_remove(self.selected), mode))
+        } else {
+            None // No project selected
+        }
+    }
+}
 
-            // Split the window and make it 10% height
-            Command::new("tmux")
-                .args(["split-window", "-v", "-l", "10%", "-c", &project.directory])
-                .status()?;
+fn render_header(frame: &mut Frame, app: &App, area: Rect) {
+    // Show different help text based on search state
+    let help_text = if app.search_active {
+        Line::styled(
+            format!(
+                "Search: {} ({} to cancel)",
+                app.search_query,
+                app.keymap.hint(Action::Quit, Context::Search)
+            ),
+            app.theme.search,
+        )
+    } else {
+        let hint = |action| app.keymap.hint(action, Context::Browser);
+        Line::styled(
+            format!(
+                "Project Browser ({}/{} move, {} search, {} sort, {} language, {} group, {} preview, {} open, {} help)",
+                hint(Action::Down),
+                hint(Action::Up),
+                hint(Action::Search),
+                hint(Action::CycleSort),
+                hint(Action::FilterLanguage),
+                hint(Action::CycleGrouping),
+                hint(Action::TogglePreview),
+                hint(Action::Open),
+                hint(Action::Help)
+            ),
+            app.theme.header,
+        )
+    };
+
+    let mut header = Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border);
+    if let Some(scan) = &app.scan {
+        header = header.title(scan.status(&app.theme).right_aligned());
+    }
 
-            // Select the top pane
-            Command::new("tmux")
-                .args(["select-pane", "-t", "1"])
-                .status()?;
+    frame.render_widget(
+        Paragraph::new(help_text)
+            .block(header)
+            .alignment(Alignment::Center),
+        area,
+    );
+}
 
-            // Launch nvim in the top pane
-            Command::new("tmux")
-                .args(["send-keys", "nvim .", "C-m"])
-                .status()?;
+fn render_list(frame: &mut Frame, app: &mut App, area: Rect) {
+    // Format with fixed-width columns for alignment
+    // Calculate dynamic column widths based on available space
+    let available_width = (area.width as usize).saturating_sub(4); // 2 for borders, 2 for spacing
+    let icon_width = languages::icon_width();
+    // Git columns only fit when there is room left for names and paths
+    let show_git = available_width >= 80;
+    let git_width = if show_git {
+        BRANCH_WIDTH + STATE_WIDTH + AGE_WIDTH + 3
+    } else {
+        0
+    };
+    // Tiny terminals leave nothing for the columns rather than underflowing
+    let text_width = available_width
+        .saturating_sub(icon_width)
+        .saturating_sub(git_width);
+    let name_ratio = 0.35; // Name gets 35% of remaining space
+    let name_width = (text_width as f64 * name_ratio) as usize;
+    let path_width = text_width.saturating_sub(name_width);
 
-            // Go back to previous window
-            Command::new("tmux").args(["last-window"]).status()?;
+    let highlight_style = app.theme.highlight;
 
-            // Kill the new window
-            Command::new("tmux").args(["kill-window"]).status()?;
+    let items: Vec<ListItem> = app
+        .rows
+        .iter()
+        .map(|row| {
+            let idx = match row {
+                Row::Group { key, count } => return group_item(app, key, *count),
+                Row::Project(idx) => *idx,
+            };
+            let project = &app.projects[idx];
+            let icon = languages::icon_for(&project.language);
 
-            Ok(())
-        } else {
-            Ok(()) // No project selected
+            let style = if app.is_selected(row) {
+                app.theme.selection
+            } else {
+                Style::default()
+            };
+
+            let highlight = app.highlights.get(&idx);
+            let name_positions = highlight.map_or(&[][..], |m| &m.name_positions);
+            let directory_positions = highlight.map_or(&[][..], |m| &m.directory_positions);
+            let (path, path_positions) =
+                prettify_home_positions(&project.directory, directory_positions);
+
+            let mut spans = vec![
+                Span::styled(
+                    format!("{:width$}", icon, width = icon_width),
+                    icon_style(&project.language),
+                ),
+                Span::raw(" "),
+            ];
+            let name_style = if project.hidden {
+                Style::default().add_modifier(Modifier::DIM)
+            } else {
+                Style::default()
+            };
+            spans.extend(truncate_highlighted(
+                &project.name,
+                name_positions,
+                name_width,
+                name_style,
+                highlight_style,
+            ));
+            spans.push(Span::raw(" "));
+            spans.extend(truncate_highlighted(
+                &path,
+                &path_positions,
+                path_width,
+                app.theme.path,
+                highlight_style,
+            ));
+
+            if show_git {
+                let status = app.git.get(&project.directory);
+                let branch = status
+                    .map(|status| status.branch.as_deref().unwrap_or("(detached)"))
+                    .unwrap_or_default();
+                let state = status.map(format_git_state).unwrap_or_default();
+                let age = status
+                    .and_then(|status| status.last_commit_time)
+                    .map(format_age)
+                    .unwrap_or_default();
+                spans.push(Span::raw(format!(
+                    " {} {} {:>age_width$}",
+                    truncate_str(branch, BRANCH_WIDTH),
+                    truncate_str(&state, STATE_WIDTH),
+                    age,
+                    age_width = AGE_WIDTH
+                )));
+            }
+
+            ListItem::new(Line::from(spans)).style(style)
+        })
+        .collect();
+
+    // Count projects rather than rows so group headers don't skew it
+    let total = app.filtered_indices.len();
+    let counter = match (app.selected_project(), app.selected_position()) {
+        (Some(_), Some(position)) => {
+            // In display order, so projects in folded groups above still count
+            let before: usize = app.rows[..position]
+                .iter()
+                .map(|row| match row {
+                    Row::Project(_) => 1,
+                    Row::Group { key, count } if app.collapsed.contains(key) => *count,
+                    Row::Group { .. } => 0,
+                })
+                .sum();
+            format!(" {} of {} ", before + 1, total)
+        }
+        _ => format!(" {} projects ", total),
+    };
+
+    let mut title = format!("Projects (by {}", app.sort.label());
+    if let Some(language) = &app.language_filter {
+        title.push_str(&format!(", {} only", language));
+    }
+    if app.group_by != GroupBy::None {
+        title.push_str(&format!(", grouped by {}", app.group_by.label()));
+    }
+    if app.show_hidden {
+        title.push_str(", hidden shown");
+    }
+    title.push(')');
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border)
+        .title(Span::styled(title, app.theme.header))
+        .title(Line::styled(counter, app.theme.header).right_aligned());
+    let projects_list = List::new(items).block(block);
+
+    let position = app.selected_position();
+    app.page_height = area.height.saturating_sub(2) as usize;
+    app.list_area = area;
+    app.list_state.select(position);
+    frame.render_stateful_widget(projects_list, area, &mut app.list_state);
+
+    // Only worth a scrollbar when the list doesn't fit
+    if app.rows.len() > app.page_height {
+        let mut scrollbar_state =
+            ScrollbarState::new(app.rows.len()).position(position.unwrap_or(0));
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+fn group_item(app: &App, key: &str, count: usize) -> ListItem<'static> {
+    let marker = if app.collapsed.contains(key) {
+        "▸"
+    } else {
+        "▾"
+    };
+    let mut spans = vec![Span::raw(format!("{} ", marker))];
+    if app.group_by == GroupBy::Language {
+        spans.push(Span::styled(
+            format!("{} ", languages::icon_for(key)),
+            icon_style(key),
+        ));
+    }
+    spans.push(Span::raw(format!("{} ({})", key, count)));
+
+    let mut style = Style::default().add_modifier(Modifier::BOLD);
+    if app.selected_group.as_deref() == Some(key) {
+        style = style.patch(app.theme.selection);
+    }
+    ListItem::new(Line::from(spans)).style(style)
+}
+
+fn render_language_chooser(
+    frame: &mut Frame,
+    chooser: &mut LanguageChooser,
+    theme: &Theme,
+    area: Rect,
+) {
+    let height = (chooser.options.len() as u16 + 2).min(area.height);
+    let width = 40.min(area.width);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let items: Vec<ListItem> = chooser
+        .options
+        .iter()
+        .map(|(language, count)| {
+            let width = languages::icon_width();
+            let line = match language {
+                Some(language) => Line::from(vec![
+                    Span::styled(
+                        format!("{:width$}", languages::icon_for(language), width = width),
+                        icon_style(language),
+                    ),
+                    Span::raw(format!(" {} ({})", language, count)),
+                ]),
+                None => Line::from(format!(
+                    "{:width$} All languages ({})",
+                    "",
+                    count,
+                    width = width
+                )),
+            };
+            ListItem::new(line)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border)
+                .title(Span::styled("Filter by language", theme.header)),
+        )
+        .highlight_style(theme.selection);
+
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut chooser.state);
+}
+
+/// Popup listing every browser action and the keys bound to it.
+fn render_help(frame: &mut Frame, keymap: &Keymap, theme: &Theme, area: Rect) {
+    let bindings: Vec<(String, &str)> = Action::ALL
+        .into_iter()
+        .filter(|action| action.is_available(Context::Browser))
+        .map(|action| (keymap.keys_for(action).join(", "), action.label()))
+        .filter(|(keys, _)| !keys.is_empty())
+        .collect();
+    let keys_width = bindings
+        .iter()
+        .map(|(keys, _)| keys.width())
+        .max()
+        .unwrap_or(0);
+
+    let lines: Vec<Line> = bindings
+        .iter()
+        .map(|(keys, label)| {
+            Line::from(vec![
+                Span::styled(
+                    format!(" {:width$}", keys, width = keys_width),
+                    theme.search,
+                ),
+                Span::raw(format!("  {}", label)),
+            ])
+        })
+        .collect();
+
+    let title = "Key bindings (any key to close)";
+    let content_width = lines.iter().map(Line::width).max().unwrap_or(0);
+    let width = (content_width.max(title.len()) as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let help = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border)
+            .title(Span::styled(title, theme.header)),
+    );
+    frame.render_widget(Clear, popup);
+    frame.render_widget(help, popup);
+}
+
+fn render_preview(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border)
+        .title(Span::styled("Preview", app.theme.header));
+    let Some(project) = app.selected_project() else {
+        frame.render_widget(block, area);
+        return;
+    };
+
+    let heading = Style::default().add_modifier(Modifier::BOLD);
+    let dim = Style::default().add_modifier(Modifier::DIM);
+
+    let mut lines = vec![Line::styled(project.name.clone(), heading)];
+    if let Some(description) = &project.description {
+        lines.push(Line::from(description.clone()));
+    }
+    lines.push(Line::styled(
+        prettify_home(&project.directory),
+        app.theme.path,
+    ));
+    lines.push(Line::from(format!("Language: {}", project.language)));
+    if !project.tags.is_empty() {
+        lines.push(Line::from(format!("Tags: {}", project.tags.join(", "))));
+    }
+    if let Some(editor) = &project.editor {
+        lines.push(Line::from(format!("Editor: {}", editor)));
+    }
+    if project.root.is_some() {
+        lines.push(Line::from(format!(
+            "Opens in: {}",
+            prettify_home(&project.open_directory())
+        )));
+    }
+    if project.hidden {
+        lines.push(Line::styled("Hidden", dim));
+    }
+
+    let Some(preview) = app.previews.get(&project.directory) else {
+        lines.push(Line::default());
+        lines.push(Line::styled("Loading…", dim));
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+        return;
+    };
+
+    if let Some(git) = &preview.git {
+        let branch = git.branch.as_deref().unwrap_or("(detached)");
+        let state = if git.dirty { "● dirty" } else { "✓ clean" };
+        let mut line = format!("Branch: {} {}", branch, state);
+        if let Some((ahead, behind)) = git.ahead_behind {
+            line.push_str(&format!(" ({} ahead, {} behind)", ahead, behind));
+        }
+        lines.push(Line::from(line));
+    }
+    if let Some(commit) = &preview.last_commit {
+        lines.push(Line::from(format!(
+            "Last commit: {} ({})",
+            commit.summary, commit.relative_date
+        )));
+    }
+
+    if !project.commands.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::styled("Commands", heading));
+        let width = project
+            .commands
+            .keys()
+            .map(|name| name.chars().count())
+            .max();
+        for (name, command) in &project.commands {
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {:width$}  ", name, width = width.unwrap_or(0))),
+                Span::styled(command.clone(), dim),
+            ]));
         }
     }
+
+    if !project.env.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::styled("Environment", heading));
+        for (name, value) in &project.env {
+            lines.push(Line::from(format!("  {}={}", name, value)));
+        }
+    }
+
+    if !preview.metadata.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::styled(".dexproject", heading));
+        for (key, value) in &preview.metadata {
+            lines.push(Line::from(format!("  {}: {}", key, value)));
+        }
+    }
+
+    if !preview.files.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::styled("Files", heading));
+        lines.push(Line::from(format!("  {}", preview.files.join("  "))));
+    }
+
+    if !preview.readme.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::styled("README", heading));
+        lines.extend(
+            preview
+                .readme
+                .iter()
+                .map(|line| Line::styled(format!("  {}", line), dim)),
+        );
+    }
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
 }
 
-fn run(mut terminal: DefaultTerminal, mut app: App) -> io::Result<()> {
+/// Runs the browser until the user quits or picks a project to open.
+fn run(mut terminal: DefaultTerminal, mut app: App) -> io::Result<Option<(ProjectInfo, OpenMode)>> {
     loop {
+        app.poll_scan();
+        app.poll_git();
+        app.previews.poll();
+        if app.show_preview {
+            if let Some(directory) = app.selected_project().map(|p| p.directory.clone()) {
+                app.previews.request(&directory);
+            }
+        }
+
         terminal.draw(|frame| {
             // Calculate available width
             let total_width = frame.area().width as usize;
             let min_width = 50;
-            let max_width = 120;
+            let max_width = if app.show_preview { 180 } else { 120 };
 
             // Calculate the actual width we'll use (bounded between min and max)
             let content_width = total_width.clamp(min_width, max_width);
@@ -194,105 +1329,171 @@ fn run(mut terminal: DefaultTerminal, mut app: App) -> io::Result<()> {
                 .constraints(vec![Constraint::Length(3), Constraint::Min(0)])
                 .split(outer[1]);
 
-            // Show different help text based on search state
-            let help_text = if app.search_active {
-                format!("Search: {} (Esc to cancel)", app.search_query)
+            render_header(frame, &app, layout[0]);
+
+            if app.show_preview {
+                let body = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .split(layout[1]);
+                render_list(frame, &mut app, body[0]);
+                render_preview(frame, &app, body[1]);
             } else {
-                "Project Browser (↑/k ↓/j to move, / to search, Enter to select)".to_string()
-            };
+                render_list(frame, &mut app, layout[1]);
+            }
 
-            frame.render_widget(
-                Paragraph::new(help_text)
-                    .block(Block::default().borders(Borders::ALL))
-                    .alignment(Alignment::Center),
-                layout[0],
-            );
+            if let Some(chooser) = &mut app.language_chooser {
+                render_language_chooser(frame, chooser, &app.theme, layout[1]);
+            }
+            if app.show_help {
+                render_help(frame, &app.keymap, &app.theme, layout[1]);
+            }
+        })?;
 
-            let items: Vec<ListItem> = app
-                .filtered_indices
-                .iter()
-                .map(|&idx| {
-                    let project = &app.projects[idx];
-                    let icon = Language::from_name(&project.language)
-                        .map(|l| l.icon)
-                        .unwrap_or("󰄛");
-
-                    let style = if idx == app.selected {
-                        Style::default().fg(Color::Yellow)
-                    } else {
-                        Style::default()
-                    };
-
-                    // Format with fixed-width columns for alignment
-                    // Calculate dynamic column widths based on available space
-                    let available_width = content_width.saturating_sub(3); // 3 for spacing
-                    let icon_width = 2;
-                    let name_ratio = 0.35; // Name gets 35% of remaining space
-                    let name_width = ((available_width - icon_width) as f64 * name_ratio) as usize;
-                    let path_width = available_width - icon_width - name_width;
-
-                    ListItem::new(format!(
-                        "{:2} {:<width$} {:<path_width$}",
-                        icon,
-                        truncate_str(&project.name, name_width),
-                        truncate_str(&prettify_home(&project.directory), path_width),
-                        width = name_width,
-                        path_width = path_width
-                    ))
-                    .style(style)
-                })
-                .collect();
+        // Wake up periodically while something is loading so its results get drawn
+        let busy = app.scan.is_some() || app.previews.is_loading() || app.statuses.is_loading();
+        if busy && !event::poll(Duration::from_millis(80))? {
+            continue;
+        }
 
-            let projects_list =
-                List::new(items).block(Block::default().borders(Borders::ALL).title("Projects"));
+        let event = event::read()?;
+        if let event::Event::Mouse(mouse) = event {
+            if app.show_help {
+                // Any click closes the help, like any key
+                app.show_help = mouse.kind != MouseEventKind::Down(MouseButton::Left);
+            } else if app.language_chooser.is_none() && app.handle_mouse(mouse) {
+                // A double click does whatever the open key would
+                if app.selected_group.is_some() {
+                    app.toggle_collapsed();
+                } else {
+                    let mode = app.open_mode;
+                    return Ok(app.into_selected(mode));
+                }
+            }
+            continue;
+        }
 
-            frame.render_widget(projects_list, layout[1]);
-        })?;
+        if let event::Event::Key(key) = event {
+            if app.show_help {
+                app.show_help = false;
+                continue;
+            }
 
-        if let event::Event::Key(key) = event::read()? {
-            match (key.code, key.modifiers) {
-                // If we're in search mode, handle it differently
-                (code, _mods) if app.search_active => match code {
-                    KeyCode::Esc => {
-                        app.search_active = false;
-                        app.search_query.clear();
-                        app.filter_projects();
+            let chord = KeyChord::from(key);
+            if app.search_active && app.language_chooser.is_none() {
+                match key.code {
+                    KeyCode::Char(c) if chord.is_text() => {
+                        app.update_search(c);
+                        continue;
                     }
                     KeyCode::Backspace => {
                         app.backspace_search();
+                        continue;
                     }
-                    KeyCode::Char(c) => {
-                        app.update_search(c);
-                    }
-                    KeyCode::Enter => {
-                        app.open_in_tmux()?;
-                        return Ok(());
+                    _ => {}
+                }
+            }
+
+            let context = if app.search_active {
+                Context::Search
+            } else {
+                Context::Browser
+            };
+            let Some(action) = app.resolve_key(chord, context) else {
+                continue;
+            };
+
+            if let Some(chooser) = &mut app.language_chooser {
+                match action {
+                    Action::Down => chooser.state.select_next(),
+                    Action::Up => chooser.state.select_previous(),
+                    Action::Open => {
+                        let choice = chooser
+                            .state
+                            .selected()
+                            .and_then(|pos| chooser.options.get(pos))
+                            .and_then(|(language, _)| language.clone());
+                        app.language_chooser = None;
+                        app.set_language_filter(choice);
                     }
+                    Action::Quit => app.language_chooser = None,
                     _ => {}
-                },
-                // Normal navigation mode
-                (KeyCode::Char('q'), _) | (KeyCode::Esc, _) => return Ok(()),
-                (KeyCode::Char('c'), KeyModifiers::CONTROL) => return Ok(()),
-                (KeyCode::Down | KeyCode::Char('j'), _) => app.next(),
-                (KeyCode::Up | KeyCode::Char('k'), _) => app.previous(),
-                (KeyCode::Char('/'), _) => {
-                    app.search_active = true;
                 }
-                (KeyCode::Enter, _) => {
-                    app.open_in_tmux()?;
-                    return Ok(());
+                continue;
+            }
+
+            match action {
+                // Open on a group header folds it instead of opening anything
+                Action::Open if app.selected_group.is_some() => app.toggle_collapsed(),
+                Action::OpenInSplit if app.selected_group.is_some() => {}
+                Action::Quit if app.search_active => {
+                    app.search_active = false;
+                    app.search_query.clear();
+                    app.filter_projects();
+                }
+                Action::Quit => return Ok(None),
+                Action::Down => app.next(),
+                Action::Up => app.previous(),
+                Action::PageDown => app.page_down(),
+                Action::PageUp => app.page_up(),
+                Action::HalfPageDown => app.half_page_down(),
+                Action::HalfPageUp => app.half_page_up(),
+                Action::First => app.select_first(),
+                Action::Last => app.select_last(),
+                Action::Search => app.search_active = true,
+                Action::Open => {
+                    let mode = app.open_mode;
+                    return Ok(app.into_selected(mode));
                 }
-                _ => {}
+                Action::OpenInSplit => return Ok(app.into_selected(OpenMode::Split)),
+                Action::TogglePreview => app.show_preview = !app.show_preview,
+                Action::CycleSort => app.cycle_sort(),
+                Action::FilterLanguage => app.open_language_chooser(),
+                Action::CycleGrouping => app.cycle_grouping(),
+                Action::ToggleHidden => {
+                    app.show_hidden = !app.show_hidden;
+                    app.refresh();
+                }
+                Action::ToggleGroup => app.toggle_collapsed(),
+                Action::Collapse => app.set_collapsed(true),
+                Action::Expand => app.set_collapsed(false),
+                Action::Help => app.show_help = true,
+                Action::NextField => {}
             }
         }
     }
 }
 
-pub fn main(projects: Vec<ProjectInfo>) -> io::Result<()> {
+/// Shows the project browser and returns the project the user picked, if any,
+/// with how to open it.
+pub fn main(
+    config: &Config,
+    keymap: Keymap,
+    projects: Vec<ProjectInfo>,
+    scan: Receiver<ScanEvent>,
+    frecency: HashMap<String, u64>,
+) -> io::Result<Option<(ProjectInfo, OpenMode)>> {
+    // Resolved before taking over the screen so colour warnings stay visible
+    let theme = Theme::new(&config.theme);
     let mut terminal = ratatui::init();
     terminal.clear()?;
-    let app = App::new(projects);
+    if config.mouse {
+        execute!(io::stdout(), EnableMouseCapture)?;
+    }
+    let mut app = App::new(projects, scan, frecency);
+    app.show_preview = config.preview;
+    app.theme = theme;
+    app.keymap = keymap;
+    app.open_mode = config.open_mode;
+    app.sort = config.sort;
+    app.search_fields = config.search_fields.clone();
+    app.group_by = config.group_by;
+    app.search_roots = config.search_paths.clone();
+    app.refresh();
     let app_result = run(terminal, app);
+    if config.mouse {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+    }
     ratatui::restore();
     app_result
 }